@@ -0,0 +1,135 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::{types::Address, utils::to_checksum};
+use web_sys::HtmlInputElement;
+use crate::{
+    contracts::{is_ens_name, resolve_ens_name},
+    ethereum::DappError,
+    storage::BrowserCache,
+    wallet::WalletContext,
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct AddressInputProps {
+    pub placeholder: String,
+    // the address typed or resolved, None while empty, invalid or resolving
+    pub on_change: Callback<Option<Address>>,
+}
+
+// Text input taking an hex address or an ENS name, names are resolved on the
+// selected network and the address shown next to the input to be checked.
+pub struct AddressInput {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    resolving: Option<String>, // name being resolved
+    resolved: Option<(String, Address)>, // name and its address
+    error: Option<DappError>,
+    // handle refs for DOM elements
+    input: NodeRef,
+}
+
+pub enum AddressInputMsg {
+    WalletChanged(WalletContext),
+    Changed,
+    Resolved(String, Result<Address, DappError>), // name as typed
+}
+
+impl Component for AddressInput {
+    type Message = AddressInputMsg;
+    type Properties = AddressInputProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(AddressInputMsg::WalletChanged))
+            .expect("AddressInput needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            resolving: None,
+            resolved: None,
+            error: None,
+            input: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            AddressInputMsg::WalletChanged(wallet) => {
+                let network_changed = wallet.provider != self.wallet.provider;
+                self.wallet = wallet;
+                // a name points to another address, or none, on another network
+                if network_changed && (self.resolved.is_some() || self.error.is_some()) {
+                    ctx.link().send_message(AddressInputMsg::Changed);
+                }
+                false
+            }
+            AddressInputMsg::Changed => {
+                let input = self.input.cast::<HtmlInputElement>()
+                    .map(|input| input.value())
+                    .unwrap_or_default();
+                let input = input.trim().to_string();
+                self.resolving = None;
+                self.resolved = None;
+                self.error = None;
+                if input.is_empty() {
+                    ctx.props().on_change.emit(None);
+                    return true
+                }
+                // names are only tried for what isn't an address
+                match DappError::parse_address(&input) {
+                    Ok(address) => ctx.props().on_change.emit(Some(address)),
+                    Err(_) if is_ens_name(&input) => {
+                        ctx.props().on_change.emit(None);
+                        self.resolving = Some(input.clone());
+                        let provider = self.wallet.provider.clone();
+                        ctx.link().send_future(async move {
+                            let address = resolve_ens_name(&provider, &BrowserCache, &input).await;
+                            AddressInputMsg::Resolved(input, address)
+                        });
+                    }
+                    Err(err) => {
+                        self.error = Some(err);
+                        ctx.props().on_change.emit(None);
+                    }
+                }
+                true
+            }
+            AddressInputMsg::Resolved(name, address) => {
+                // the input changed while resolving
+                if self.resolving.as_ref() != Some(&name) {
+                    return false
+                }
+                self.resolving = None;
+                match address {
+                    Ok(address) => {
+                        self.resolved = Some((name, address));
+                        ctx.props().on_change.emit(Some(address));
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <span>
+                <input
+                    type="text"
+                    placeholder={ctx.props().placeholder.clone()}
+                    ref={&self.input}
+                    onchange={ctx.link().callback(|_| AddressInputMsg::Changed)}
+                />
+                if let Some(name) = &self.resolving {
+                    <small>{format!(" resolving {}..", name)}</small>
+                }
+                if let Some((name, address)) = &self.resolved {
+                    <small>{format!(" {} is {}", name, to_checksum(address, None))}</small>
+                }
+                if let Some(err) = &self.error {
+                    <small>{format!(" {}", err)}</small>
+                }
+            </span>
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::{types::Address, utils::to_checksum};
+use crate::{
+    contracts::lookup_ens_name,
+    helpers::short_address,
+    storage::BrowserCache,
+    wallet::WalletContext,
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct AddressNameProps {
+    pub address: Address,
+}
+
+// ENS name of an address when it has a verified one, the short address
+// otherwise. The full address is in the tooltip.
+pub struct AddressName {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    name: Option<String>,
+}
+
+pub enum AddressNameMsg {
+    WalletChanged(WalletContext),
+    Lookup,
+    SetName(Address, Option<String>), // address the name belongs to
+}
+
+impl Component for AddressName {
+    type Message = AddressNameMsg;
+    type Properties = AddressNameProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(AddressNameMsg::WalletChanged))
+            .expect("AddressName needs a WalletContext provider");
+        ctx.link().send_message(AddressNameMsg::Lookup);
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            name: None,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        self.name = None;
+        ctx.link().send_message(AddressNameMsg::Lookup);
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            AddressNameMsg::WalletChanged(wallet) => {
+                let network_changed = wallet.provider != self.wallet.provider;
+                self.wallet = wallet;
+                if network_changed {
+                    self.name = None;
+                    ctx.link().send_message(AddressNameMsg::Lookup);
+                }
+                network_changed
+            }
+            AddressNameMsg::Lookup => {
+                let address = ctx.props().address;
+                let provider = self.wallet.provider.clone();
+                ctx.link().send_future(async move {
+                    match lookup_ens_name(&provider, BrowserCache, address).await {
+                        Ok(name) => AddressNameMsg::SetName(address, name),
+                        Err(err) => {
+                            log::warn!("No ENS name for {:?}: {}", address, err);
+                            AddressNameMsg::SetName(address, None)
+                        }
+                    }
+                });
+                false
+            }
+            AddressNameMsg::SetName(address, name) => {
+                if address != ctx.props().address {
+                    return false
+                }
+                self.name = name;
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let address = to_checksum(&ctx.props().address, None);
+        html! {
+            <span title={address.clone()}>
+                { self.name.clone().unwrap_or_else(|| short_address(&address)) }
+            </span>
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::{types::{Address, U256}, utils::format_units};
+use web_sys::HtmlInputElement;
+use std::sync::Arc;
+use crate::{
+    components::{address_name::AddressName, tx_card::TxCard},
+    contracts::{approve_erc20, is_unlimited, scan_approvals, TokenApproval, IERC20_ABI},
+    ethereum::{block_range, ensure_chain, DappError, TxStatus},
+    helpers::short_address,
+    wallet::{Eip1193, WalletContext},
+};
+
+// blocks scanned when the start block is left blank
+const DEFAULT_SCAN_BLOCKS: u64 = 50_000;
+
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct ApprovalScannerProps {
+    #[prop_or(DEFAULT_SCAN_BLOCKS)]
+    pub default_blocks: u64, // used when the start block is left blank
+}
+
+pub struct ApprovalScanner {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    scanning: bool,
+    range: Option<(u64, u64)>,
+    scanned_to: Option<u64>,
+    approvals: Option<Vec<TokenApproval>>, // None until the first scan
+    revoking: Option<(Address, Address)>, // token and spender of the revoke in flight
+    error: Option<DappError>,
+    tx: Option<String>,
+    // handle refs for DOM elements
+    from_block: NodeRef,
+    to_block: NodeRef,
+}
+
+pub enum ApprovalScannerMsg {
+    WalletChanged(WalletContext),
+    Scan,
+    SetRange(u64, u64),
+    Progress(u64),
+    SetApprovals(String, u64, Vec<TokenApproval>), // account and chain scanned
+    Revoke(Address, Address),
+    SetTx(String),
+    TxDone(TxStatus),
+    SetError(DappError),
+}
+
+impl Component for ApprovalScanner {
+    type Message = ApprovalScannerMsg;
+    type Properties = ApprovalScannerProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(ApprovalScannerMsg::WalletChanged))
+            .expect("ApprovalScanner needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            scanning: false,
+            range: None,
+            scanned_to: None,
+            approvals: None,
+            revoking: None,
+            error: None,
+            tx: None,
+            from_block: NodeRef::default(),
+            to_block: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            ApprovalScannerMsg::WalletChanged(wallet) => {
+                if wallet.address != self.wallet.address || wallet.provider != self.wallet.provider {
+                    self.approvals = None;
+                    self.range = None;
+                    self.scanned_to = None;
+                    self.scanning = false;
+                    self.tx = None;
+                    self.revoking = None;
+                }
+                self.wallet = wallet;
+                true
+            }
+            ApprovalScannerMsg::Scan => {
+                self.error = None;
+                let account = match self.wallet.address.clone() {
+                    Some(account) => account,
+                    None => return false
+                };
+                let owner = match DappError::parse_address(&account) {
+                    Ok(owner) => owner,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                let (from_block, to_block) = match (block_input(&self.from_block), block_input(&self.to_block)) {
+                    (Ok(from), Ok(to)) => (from, to),
+                    (Err(err), _) | (_, Err(err)) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                self.scanning = true;
+                self.scanned_to = None;
+                let chain_id = self.wallet.network().chain_id;
+                let provider = self.wallet.provider.clone();
+                let default_blocks = ctx.props().default_blocks;
+                let on_range = ctx.link().callback(|(from, to)| ApprovalScannerMsg::SetRange(from, to));
+                let on_progress = ctx.link().callback(ApprovalScannerMsg::Progress);
+                ctx.link().send_future(async move {
+                    let client = match provider.get().await {
+                        Ok(client) => client,
+                        Err(err) => return ApprovalScannerMsg::SetError(err)
+                    };
+                    let (from_block, to_block) = match block_range(&client, from_block, to_block, default_blocks).await {
+                        Ok(range) => range,
+                        Err(err) => return ApprovalScannerMsg::SetError(err)
+                    };
+                    on_range.emit((from_block, to_block));
+                    match scan_approvals(client, owner, from_block, to_block, move |block| on_progress.emit(block)).await {
+                        Ok(approvals) => ApprovalScannerMsg::SetApprovals(account, chain_id, approvals),
+                        Err(err) => ApprovalScannerMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            ApprovalScannerMsg::SetRange(from, to) => {
+                self.range = Some((from, to));
+                true
+            }
+            ApprovalScannerMsg::Progress(block) => {
+                self.scanned_to = Some(block);
+                true
+            }
+            ApprovalScannerMsg::SetApprovals(account, chain_id, approvals) => {
+                // the account or the network changed while scanning
+                if self.wallet.address.as_ref() != Some(&account) || self.wallet.network().chain_id != chain_id {
+                    return false
+                }
+                self.scanning = false;
+                self.approvals = Some(approvals);
+                true
+            }
+            ApprovalScannerMsg::Revoke(token, spender) => {
+                self.error = None;
+                let from = match DappError::parse_address(self.wallet.address.as_deref().unwrap_or_default()) {
+                    Ok(from) => from,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                self.revoking = Some((token, spender));
+                let chain_id = self.wallet.network().chain_id;
+                ctx.link().send_future(async move {
+                    let client = Arc::new(Eip1193::provider());
+                    if let Err(err) = ensure_chain(&client, chain_id).await {
+                        return ApprovalScannerMsg::SetError(err)
+                    }
+                    match approve_erc20(client, token, from, spender, U256::zero()).await {
+                        Ok(tx) => ApprovalScannerMsg::SetTx(format!("{:?}", tx)),
+                        Err(err) => ApprovalScannerMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            ApprovalScannerMsg::SetTx(hash) => {
+                self.tx = Some(hash);
+                true
+            }
+            ApprovalScannerMsg::TxDone(status) => {
+                let revoked = self.revoking.take();
+                if let (TxStatus::Confirmed(_), Some(pair), Some(approvals)) = (status, revoked, self.approvals.as_mut()) {
+                    approvals.retain(|approval| (approval.token, approval.spender) != pair);
+                }
+                true
+            }
+            ApprovalScannerMsg::SetError(err) => {
+                log::error!("{:?}", err);
+                self.scanning = false;
+                self.revoking = None;
+                self.error = Some(err);
+                true
+            }
+        }
+    }
+
+    // html! checks the TxCard props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        if self.wallet.address.is_none() {
+            return html! { <p>{"Connect a wallet to scan its approvals"}</p> }
+        }
+        html! {
+            <div>
+                <h3>{"Token approvals"}</h3>
+                <input type="number" placeholder={format!("From block (last {} blocks)", ctx.props().default_blocks)} ref={&self.from_block} />
+                <input type="number" placeholder="To block (latest)" ref={&self.to_block} />
+                <button disabled={self.scanning} onclick={link.callback(|_| ApprovalScannerMsg::Scan)}>
+                    {"Scan"}
+                </button>
+                if let Some((from, to)) = self.range {
+                    <p>
+                        { format!("Blocks {} to {}", from, to) }
+                        if self.scanning {
+                            { format!(", scanned up to {}", self.scanned_to.unwrap_or(from)) }
+                        }
+                    </p>
+                }
+                if let Some(error) = &self.error {
+                    <p>{ scanner_error_message(error) }</p>
+                }
+                if let Some(approvals) = &self.approvals {
+                    if approvals.is_empty() {
+                        <p>{"No live approvals in this range"}</p>
+                    }
+                    <table>
+                        { for approvals.iter().map(|approval| self.view_approval(ctx, approval)) }
+                    </table>
+                }
+                if let Some(hash) = &self.tx {
+                    <TxCard
+                        key={hash.clone()}
+                        hash={hash.clone()}
+                        abi={Some(IERC20_ABI.clone())}
+                        on_done={link.callback(ApprovalScannerMsg::TxDone)}
+                    />
+                }
+            </div>
+        }
+    }
+}
+
+impl ApprovalScanner {
+    fn view_approval(&self, ctx: &Context<Self>, approval: &TokenApproval) -> Html {
+        let token = format!("{:?}", approval.token);
+        let spender = format!("{:?}", approval.spender);
+        let symbol = approval.symbol.clone().unwrap_or_else(|| short_address(&token));
+        let amount = if is_unlimited(approval.allowance) {
+            "Unlimited".to_string()
+        } else {
+            match approval.decimals {
+                Some(decimals) => format_units(approval.allowance, decimals as u32).unwrap_or_default(),
+                None => approval.allowance.to_string(),
+            }
+        };
+        let pair = (approval.token, approval.spender);
+        html! {
+            <tr key={format!("{}-{}", token, spender)}>
+                <td>
+                    <a href={self.wallet.network().explorer_token(&token)} target="_blank">{symbol}</a>
+                </td>
+                <td><AddressName address={approval.spender} /></td>
+                <td>
+                    if is_unlimited(approval.allowance) {
+                        <strong>{amount}</strong>
+                    } else {
+                        {amount}
+                    }
+                </td>
+                <td>
+                    <button
+                        disabled={self.revoking.is_some()}
+                        onclick={ctx.link().callback(move |_| ApprovalScannerMsg::Revoke(pair.0, pair.1))}
+                    >
+                        {"Revoke"}
+                    </button>
+                </td>
+            </tr>
+        }
+    }
+}
+
+// blank inputs are left for block_range to fill
+fn block_input(input: &NodeRef) -> Result<Option<u64>, DappError> {
+    let value = input.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+    if value.trim().is_empty() {
+        return Ok(None)
+    }
+    value.trim()
+        .parse()
+        .map(Some)
+        .map_err(|_| DappError::InvalidInput(format!("\"{}\" is not a block number", value)))
+}
+
+fn scanner_error_message(error: &DappError) -> String {
+    match error {
+        DappError::WalletRejected => "Revoke cancelled in the wallet".to_string(),
+        error => error.to_string(),
+    }
+}
@@ -1,2 +1,16 @@
+pub mod address_input;
+pub mod address_name;
+pub mod approval_scanner;
+pub mod send_native;
+pub mod sign_in;
+pub mod sign_message;
+pub mod signature_check;
+pub mod token_allowances;
 pub mod token_card;
-pub mod tx_card;
\ No newline at end of file
+pub mod token_form;
+pub mod token_list_picker;
+pub mod token_permit;
+pub mod transfer_history;
+pub mod tx_card;
+pub mod typed_data_signer;
+pub mod wallet_picker;
\ No newline at end of file
@@ -0,0 +1,285 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::{types::{Address, Eip1559TransactionRequest, U256}, utils::{format_units, parse_units}};
+use web_sys::HtmlInputElement;
+use crate::{
+    components::{address_input::AddressInput, token_form::form_error_message, tx_card::TxCard},
+    ethereum::{ensure_chain, estimate_gas, suggest_fees, DappError, FeeSpeed, FeeSuggestions, TxStatus},
+    storage::Settings,
+    wallet::{send_transaction, Eip1193, WalletContext},
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct SendNativeProps {
+    #[prop_or_default]
+    pub on_done: Callback<TxStatus>, // final status of each sent transaction
+}
+
+// recipient and value the estimate was made for
+#[derive(Clone, Debug, PartialEq)]
+pub struct Draft {
+    to: Address,
+    value: U256,
+}
+
+pub struct SendNative {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    draft: Option<Draft>,
+    gas: Option<U256>,
+    fees: Option<FeeSuggestions>,
+    speed: FeeSpeed,
+    error: Option<DappError>,
+    sending: bool,
+    tx: Option<String>,
+    to: Option<Address>, // recipient typed or resolved
+    // handle refs for DOM elements
+    amount: NodeRef,
+}
+
+pub enum SendNativeMsg {
+    WalletChanged(WalletContext),
+    SetRecipient(Option<Address>),
+    Estimate,
+    SetEstimate(Draft, U256, FeeSuggestions),
+    SelectSpeed(FeeSpeed),
+    Send,
+    SetTx(String),
+    SetError(DappError),
+}
+
+impl Component for SendNative {
+    type Message = SendNativeMsg;
+    type Properties = SendNativeProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(SendNativeMsg::WalletChanged))
+            .expect("SendNative needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            draft: None,
+            gas: None,
+            fees: None,
+            speed: Settings::load().preferences.fee_speed,
+            error: None,
+            sending: false,
+            tx: None,
+            to: None,
+            amount: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            SendNativeMsg::WalletChanged(wallet) => {
+                let changed = wallet.address != self.wallet.address
+                    || wallet.provider != self.wallet.provider;
+                self.wallet = wallet;
+                if changed {
+                    self.gas = None;
+                    self.fees = None;
+                    ctx.link().send_message(SendNativeMsg::Estimate);
+                }
+                true
+            }
+            SendNativeMsg::SetRecipient(to) => {
+                self.to = to;
+                ctx.link().send_message(SendNativeMsg::Estimate);
+                false
+            }
+            SendNativeMsg::Estimate => {
+                self.error = None;
+                self.draft = None;
+                self.gas = None;
+                let draft = match self.read_draft() {
+                    Ok(Some(draft)) => draft,
+                    Ok(None) => return true,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                let from = match self.from() {
+                    Ok(from) => from,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                self.draft = Some(draft.clone());
+                let provider = self.wallet.provider.clone();
+                ctx.link().send_future(async move {
+                    let client = match provider.get().await {
+                        Ok(client) => client,
+                        Err(err) => return SendNativeMsg::SetError(err)
+                    };
+                    let tx = Eip1559TransactionRequest::new()
+                        .from(from)
+                        .to(draft.to)
+                        .value(draft.value);
+                    let (gas, fees) = futures::join!(estimate_gas(&client, &tx), suggest_fees(&client));
+                    match (gas, fees) {
+                        (Ok(gas), Ok(fees)) => SendNativeMsg::SetEstimate(draft, gas, fees),
+                        (Err(err), _) | (_, Err(err)) => SendNativeMsg::SetError(err),
+                    }
+                });
+                true
+            }
+            SendNativeMsg::SetEstimate(draft, gas, fees) => {
+                // the inputs changed while estimating
+                if self.draft.as_ref() != Some(&draft) {
+                    return false
+                }
+                self.gas = Some(gas);
+                self.fees = Some(fees);
+                true
+            }
+            SendNativeMsg::SelectSpeed(speed) => {
+                self.speed = speed;
+                Settings::update(|settings| settings.preferences.fee_speed = speed);
+                true
+            }
+            SendNativeMsg::Send => {
+                let (draft, gas, fees) = match (&self.draft, self.gas, &self.fees) {
+                    (Some(draft), Some(gas), Some(fees)) => (draft.clone(), gas, fees.get(self.speed)),
+                    _ => return false
+                };
+                let from = match self.from() {
+                    Ok(from) => from,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                self.sending = true;
+                self.error = None;
+                let chain_id = self.wallet.network().chain_id;
+                ctx.link().send_future(async move {
+                    if let Err(err) = ensure_chain(&Eip1193::provider(), chain_id).await {
+                        return SendNativeMsg::SetError(err)
+                    }
+                    let tx = Eip1559TransactionRequest::new()
+                        .from(from)
+                        .to(draft.to)
+                        .value(draft.value)
+                        .gas(gas)
+                        .max_fee_per_gas(fees.max_fee_per_gas)
+                        .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+                        .chain_id(chain_id);
+                    match send_transaction(tx).await {
+                        Ok(hash) => SendNativeMsg::SetTx(format!("{:?}", hash)),
+                        Err(err) => SendNativeMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            SendNativeMsg::SetTx(hash) => {
+                log::info!("Native transfer sent: {}", hash);
+                self.sending = false;
+                self.tx = Some(hash);
+                true
+            }
+            SendNativeMsg::SetError(err) => {
+                log::error!("{:?}", err);
+                self.sending = false;
+                self.error = Some(err);
+                true
+            }
+        }
+    }
+
+    // html! checks the AddressInput and TxCard props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let network = self.wallet.network();
+        let link = ctx.link();
+        html! {
+            <div>
+                <p>{format!("Send {}", network.native_symbol)}</p>
+                <AddressInput
+                    placeholder="Recipient"
+                    on_change={link.callback(SendNativeMsg::SetRecipient)}
+                />
+                <input
+                    type="number"
+                    placeholder="Amount"
+                    ref={&self.amount}
+                    onchange={link.callback(|_| SendNativeMsg::Estimate)}
+                />
+                if let (Some(draft), Some(gas), Some(fees)) = (&self.draft, self.gas, &self.fees) {
+                    <div>
+                        { for FeeSpeed::ALL.iter().map(|speed| {
+                            let speed = *speed;
+                            html! {
+                                <label>
+                                    <input
+                                        type="radio"
+                                        name="fee-speed"
+                                        checked={speed == self.speed}
+                                        onchange={link.callback(move |_| SendNativeMsg::SelectSpeed(speed))}
+                                    />
+                                    { format!("{} ({} gwei tip)", speed.label(), gwei(fees.get(speed).max_priority_fee_per_gas)) }
+                                </label>
+                            }
+                        })}
+                        <p>
+                            { format!(
+                                "Gas: {}, base fee: {} gwei, max network fee: {} {}",
+                                gas,
+                                gwei(fees.base_fee),
+                                self.format_native(gas * fees.get(self.speed).max_fee_per_gas),
+                                network.native_symbol
+                            ) }
+                            <br />
+                            { format!(
+                                "Total cost (at most): {} {}",
+                                self.format_native(draft.value + gas * fees.get(self.speed).max_fee_per_gas),
+                                network.native_symbol
+                            ) }
+                        </p>
+                        <button disabled={self.sending} onclick={link.callback(|_| SendNativeMsg::Send)}>
+                            {"Send"}
+                        </button>
+                    </div>
+                }
+                if let Some(error) = &self.error {
+                    <p>{ form_error_message(error, "transfer") }</p>
+                }
+                if let Some(hash) = &self.tx {
+                    <TxCard
+                        key={hash.clone()}
+                        hash={hash.clone()}
+                        on_done={ctx.props().on_done.clone()}
+                    />
+                }
+            </div>
+        }
+    }
+}
+
+impl SendNative {
+    // None until both inputs are filled, the recipient input shows its own errors
+    fn read_draft(&self) -> Result<Option<Draft>, DappError> {
+        let amount = self.amount.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+        let to = match self.to {
+            Some(to) if !amount.trim().is_empty() => to,
+            _ => return Ok(None)
+        };
+        let value = parse_units(amount.trim(), self.wallet.network().decimals)
+            .map_err(|_| DappError::InvalidInput(format!("\"{}\" is not a valid amount", amount)))?;
+        Ok(Some(Draft { to, value }))
+    }
+
+    fn from(&self) -> Result<Address, DappError> {
+        DappError::parse_address(self.wallet.address.as_deref().unwrap_or_default())
+    }
+
+    fn format_native(&self, amount: U256) -> String {
+        format_units(amount, self.wallet.network().decimals).unwrap_or_default()
+    }
+}
+
+fn gwei(amount: U256) -> String {
+    format_units(amount, "gwei").unwrap_or_default()
+}
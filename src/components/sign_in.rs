@@ -0,0 +1,83 @@
+use yew::{prelude::*, context::ContextHandle};
+use web_sys::HtmlInputElement;
+use crate::{
+    components::address_name::AddressName,
+    wallet::WalletContext,
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct SignInProps {
+    pub on_sign_in: Callback<Option<String>>, // statement shown in the message
+    pub on_sign_out: Callback<()>,
+    #[prop_or_default]
+    pub signing: bool, // waiting for the wallet or the verifier
+}
+
+pub struct SignIn {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    // handle refs for DOM elements
+    statement: NodeRef,
+}
+
+pub enum SignInMsg {
+    WalletChanged(WalletContext),
+    SignIn,
+}
+
+impl Component for SignIn {
+    type Message = SignInMsg;
+    type Properties = SignInProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(SignInMsg::WalletChanged))
+            .expect("SignIn needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            statement: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            SignInMsg::WalletChanged(wallet) => {
+                let changed = wallet.session != self.wallet.session
+                    || wallet.address != self.wallet.address;
+                self.wallet = wallet;
+                changed
+            }
+            SignInMsg::SignIn => {
+                let statement = self.statement.cast::<HtmlInputElement>().map(|input| input.value());
+                ctx.props().on_sign_in.emit(statement);
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_sign_out = ctx.props().on_sign_out.reform(|_| ());
+        html! {
+            <div>
+                if let Some(session) = &self.wallet.session {
+                    {"Signed in as "}<AddressName address={session.address} />{format!(" on chain {}", session.chain_id)}
+                    if let Some(expiration) = &session.expiration_time {
+                        <small>{format!(" until {}", expiration)}</small>
+                    }
+                    <button onclick={on_sign_out}>{"Sign out"}</button>
+                } else if self.wallet.address.is_some() {
+                    <input
+                        type="text"
+                        ref={&self.statement}
+                        placeholder="Statement (optional)"
+                    />
+                    <button disabled={ctx.props().signing} onclick={link.callback(|_| SignInMsg::SignIn)}>
+                        { if ctx.props().signing { "Signing in.." } else { "Sign in with Ethereum" } }
+                    </button>
+                }
+            </div>
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::types::{Address, Signature};
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use crate::{
+    components::{address_input::AddressInput, signature_check::{signer_check, view_verified, Verified}},
+    ethereum::{message_bytes, parse_signature, recover_signer, DappError, MessageFormat},
+    wallet::{personal_sign, WalletContext},
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct SignMessageProps {
+    #[prop_or_default]
+    pub on_signed: Callback<(Vec<u8>, Signature)>, // signed bytes and signature
+}
+
+// last signature made in the panel
+struct Signed {
+    signature: Signature,
+    signer: Result<Address, DappError>, // recovered from the signature
+}
+
+pub struct SignMessage {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    format: MessageFormat,
+    signing: bool,
+    signed: Option<Signed>,
+    error: Option<DappError>,
+    verified: Option<Verified>,
+    verify_address: Option<Address>, // typed or resolved
+    // handle refs for DOM elements
+    message: NodeRef,
+    verify_message: NodeRef,
+    verify_signature: NodeRef,
+}
+
+pub enum SignMessageMsg {
+    WalletChanged(WalletContext),
+    SetFormat(MessageFormat),
+    Sign,
+    Signed(Vec<u8>, Signature),
+    SetError(DappError),
+    SetVerifyAddress(Option<Address>),
+    Verify,
+}
+
+impl Component for SignMessage {
+    type Message = SignMessageMsg;
+    type Properties = SignMessageProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(SignMessageMsg::WalletChanged))
+            .expect("SignMessage needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            format: MessageFormat::Text,
+            signing: false,
+            signed: None,
+            error: None,
+            verified: None,
+            verify_address: None,
+            message: NodeRef::default(),
+            verify_message: NodeRef::default(),
+            verify_signature: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            SignMessageMsg::WalletChanged(wallet) => {
+                let changed = wallet.address != self.wallet.address;
+                self.wallet = wallet;
+                if changed {
+                    // made with the previous account
+                    self.signed = None;
+                    self.error = None;
+                }
+                changed
+            }
+            SignMessageMsg::SetFormat(format) => {
+                self.format = format;
+                true
+            }
+            SignMessageMsg::Sign => {
+                self.error = None;
+                self.signed = None;
+                let address = match self.wallet.address.as_deref().map(DappError::parse_address) {
+                    Some(Ok(address)) => address,
+                    _ => return false
+                };
+                let input = self.message.cast::<HtmlTextAreaElement>().map(|input| input.value()).unwrap_or_default();
+                let message = match message_bytes(&input, self.format) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                self.signing = true;
+                ctx.link().send_future(async move {
+                    match personal_sign(&message, address).await {
+                        Ok(signature) => SignMessageMsg::Signed(message, signature),
+                        Err(err) => SignMessageMsg::SetError(err),
+                    }
+                });
+                true
+            }
+            SignMessageMsg::Signed(message, signature) => {
+                self.signing = false;
+                self.signed = Some(Signed {
+                    signer: recover_signer(&message, &signature),
+                    signature,
+                });
+                ctx.props().on_signed.emit((message, signature));
+                true
+            }
+            SignMessageMsg::SetError(err) => {
+                self.signing = false;
+                self.error = Some(err);
+                true
+            }
+            SignMessageMsg::SetVerifyAddress(address) => {
+                self.verify_address = address;
+                false
+            }
+            SignMessageMsg::Verify => {
+                let message = self.verify_message.cast::<HtmlTextAreaElement>().map(|input| input.value()).unwrap_or_default();
+                let signature = self.verify_signature.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+                self.verified = Some(message_bytes(&message, self.format).and_then(|message| {
+                    let signature = parse_signature(&signature)?;
+                    let expected = self.verify_address.ok_or_else(|| DappError::MissingAddress("signer".to_string()))?;
+                    Ok((recover_signer(&message, &signature)?, expected))
+                }));
+                true
+            }
+        }
+    }
+
+    // html! checks the AddressInput props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        html! {
+            <div>
+                <h3>{"Sign a message"}</h3>
+                <div>
+                    { for MessageFormat::ALL.iter().map(|format| {
+                        let format = *format;
+                        html! {
+                            <label>
+                                <input
+                                    type="radio"
+                                    name="message-format"
+                                    checked={format == self.format}
+                                    onchange={link.callback(move |_| SignMessageMsg::SetFormat(format))}
+                                />
+                                { format.label() }
+                            </label>
+                        }
+                    })}
+                </div>
+                if self.wallet.address.is_some() {
+                    <div>
+                        <textarea ref={&self.message} placeholder="Message to sign" />
+                        <button disabled={self.signing} onclick={link.callback(|_| SignMessageMsg::Sign)}>
+                            { if self.signing { "Waiting for the wallet.." } else { "Sign" } }
+                        </button>
+                    </div>
+                }
+                if let Some(err) = &self.error {
+                    <p>{err.to_string()}</p>
+                }
+                if let Some(signed) = &self.signed {
+                    <p>
+                        {"Signature: "}<code>{format!("0x{}", signed.signature)}</code>
+                        <br />
+                        { signer_check(&self.wallet, &signed.signer) }
+                    </p>
+                }
+                <h4>{"Verify a signature"}</h4>
+                <div>
+                    <textarea ref={&self.verify_message} placeholder="Signed message" />
+                    <input type="text" ref={&self.verify_signature} placeholder="Signature" />
+                    <AddressInput placeholder="Signer address" on_change={link.callback(SignMessageMsg::SetVerifyAddress)} />
+                    <button onclick={link.callback(|_| SignMessageMsg::Verify)}>
+                        {"Verify"}
+                    </button>
+                </div>
+                { view_verified(&self.verified) }
+            </div>
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use yew::prelude::*;
+use ethers::core::types::Address;
+use crate::{ethereum::DappError, wallet::WalletContext};
+
+// recovered signer and expected address of a verify form
+pub type Verified = Result<(Address, Address), DappError>;
+
+// the wallet signed with the connected account
+pub fn signer_check(wallet: &WalletContext, signer: &Result<Address, DappError>) -> String {
+    let connected = wallet.address.as_deref()
+        .and_then(|address| DappError::parse_address(address).ok());
+    match signer {
+        Ok(signer) if Some(*signer) == connected => format!("Verified, signed by {:?}", signer),
+        Ok(signer) => format!("Signed by {:?}, which is not the connected account", signer),
+        Err(err) => err.to_string(),
+    }
+}
+
+pub fn view_verified(verified: &Option<Verified>) -> Html {
+    match verified {
+        Some(Ok((signer, expected))) if signer == expected => html! {
+            <p>{format!("Valid signature from {:?}", signer)}</p>
+        },
+        Some(Ok((signer, expected))) => html! {
+            <p>{format!("Not signed by {:?}, the signer is {:?}", expected, signer)}</p>
+        },
+        Some(Err(err)) => html! { <p>{err.to_string()}</p> },
+        None => html! {},
+    }
+}
@@ -0,0 +1,358 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::{types::{Address, U256}, utils::format_units};
+use std::sync::Arc;
+use crate::{
+    components::{address_input::AddressInput, address_name::AddressName, token_form::{form_error_message, read_amount, token_and_account}, tx_card::TxCard},
+    contracts::{approve_erc20, fetch_allowances, is_unlimited, transfer_from_erc20, IERC20_ABI},
+    ethereum::{ensure_chain, DappError, TxStatus},
+    wallet::{Eip1193, WalletContext},
+};
+
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct TokenAllowancesProps {
+    pub token_address: String,
+    pub symbol: String,
+    pub decimals: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Approval {
+    Exact, // amount typed by the user, also used to reduce an allowance
+    Unlimited,
+    Revoke,
+}
+
+pub struct TokenAllowances {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    spenders: Vec<(Address, Option<U256>)>, // allowance, None while loading
+    received: Option<(Address, U256)>, // allowance of an owner to the connected account
+    error: Option<DappError>,
+    tx: Option<String>,
+    // addresses typed or resolved
+    spender: Option<Address>,
+    owner: Option<Address>,
+    recipient: Option<Address>,
+    // handle refs for DOM elements
+    approve_amount: NodeRef,
+    transfer_amount: NodeRef,
+}
+
+pub enum TokenAllowancesMsg {
+    WalletChanged(WalletContext),
+    SetSpender(Option<Address>),
+    SetOwner(Option<Address>),
+    SetRecipient(Option<Address>),
+    AddSpender,
+    RemoveSpender(Address),
+    FetchAllowances,
+    SetAllowances(Address, u64, Vec<(Address, U256)>), // owner and chain the allowances belong to
+    Approve(Address, Approval),
+    CheckReceived,
+    SetReceived(Address, Address, u64, U256), // owner, spender (the account) and chain
+    TransferFrom,
+    SetTx(String),
+    TxDone(TxStatus),
+    SetError(DappError),
+}
+
+impl Component for TokenAllowances {
+    type Message = TokenAllowancesMsg;
+    type Properties = TokenAllowancesProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(TokenAllowancesMsg::WalletChanged))
+            .expect("TokenAllowances needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            spenders: Vec::new(),
+            received: None,
+            error: None,
+            tx: None,
+            spender: None,
+            owner: None,
+            recipient: None,
+            approve_amount: NodeRef::default(),
+            transfer_amount: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TokenAllowancesMsg::WalletChanged(wallet) => {
+                let account_changed = wallet.address != self.wallet.address
+                    || wallet.provider != self.wallet.provider;
+                self.wallet = wallet;
+                if account_changed {
+                    self.received = None;
+                    self.error = None;
+                    self.tx = None;
+                    ctx.link().send_message(TokenAllowancesMsg::FetchAllowances);
+                }
+                true
+            }
+            TokenAllowancesMsg::SetSpender(spender) => {
+                self.spender = spender;
+                false
+            }
+            TokenAllowancesMsg::SetOwner(owner) => {
+                self.owner = owner;
+                ctx.link().send_message(TokenAllowancesMsg::CheckReceived);
+                false
+            }
+            TokenAllowancesMsg::SetRecipient(recipient) => {
+                self.recipient = recipient;
+                false
+            }
+            TokenAllowancesMsg::AddSpender => {
+                match self.spender {
+                    Some(spender) => {
+                        self.error = None;
+                        if !self.spenders.iter().any(|(known, _)| *known == spender) {
+                            self.spenders.push((spender, None));
+                            ctx.link().send_message(TokenAllowancesMsg::FetchAllowances);
+                        }
+                    }
+                    None => self.error = Some(DappError::MissingAddress("spender".to_string())),
+                }
+                true
+            }
+            TokenAllowancesMsg::RemoveSpender(spender) => {
+                self.spenders.retain(|(known, _)| *known != spender);
+                true
+            }
+            TokenAllowancesMsg::FetchAllowances => {
+                let (token, owner) = match token_and_account(&ctx.props().token_address, &self.wallet) {
+                    Ok(addresses) => addresses,
+                    Err(_) => return false
+                };
+                let spenders: Vec<Address> = self.spenders.iter().map(|(spender, _)| *spender).collect();
+                if spenders.is_empty() {
+                    return false
+                }
+                let chain_id = self.wallet.network().chain_id;
+                let provider = self.wallet.provider.clone();
+                ctx.link().send_future(async move {
+                    let client = match provider.get().await {
+                        Ok(client) => client,
+                        Err(err) => return TokenAllowancesMsg::SetError(err)
+                    };
+                    match fetch_allowances(client, token, owner, &spenders).await {
+                        Ok(allowances) => TokenAllowancesMsg::SetAllowances(
+                            owner,
+                            chain_id,
+                            spenders.into_iter().zip(allowances).collect(),
+                        ),
+                        Err(err) => TokenAllowancesMsg::SetError(err)
+                    }
+                });
+                false
+            }
+            TokenAllowancesMsg::SetAllowances(owner, chain_id, allowances) => {
+                // the account or the network changed while fetching
+                if !self.is_current(ctx, owner, chain_id) {
+                    return false
+                }
+                for (spender, allowance) in allowances {
+                    if let Some(entry) = self.spenders.iter_mut().find(|(known, _)| *known == spender) {
+                        entry.1 = Some(allowance);
+                    }
+                }
+                true
+            }
+            TokenAllowancesMsg::Approve(spender, approval) => {
+                self.error = None;
+                let amount = match approval {
+                    Approval::Exact => match read_amount(&self.approve_amount, ctx.props().decimals) {
+                        Ok(amount) => amount,
+                        Err(err) => {
+                            self.error = Some(err);
+                            return true
+                        }
+                    },
+                    Approval::Unlimited => U256::MAX,
+                    Approval::Revoke => U256::zero(),
+                };
+                let (token, from) = match token_and_account(&ctx.props().token_address, &self.wallet) {
+                    Ok(addresses) => addresses,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                let chain_id = self.wallet.network().chain_id;
+                ctx.link().send_future(async move {
+                    let client = Arc::new(Eip1193::provider());
+                    if let Err(err) = ensure_chain(&client, chain_id).await {
+                        return TokenAllowancesMsg::SetError(err)
+                    }
+                    // approve overwrites the allowance, reducing is an exact approve of the new amount
+                    match approve_erc20(client, token, from, spender, amount).await {
+                        Ok(tx) => TokenAllowancesMsg::SetTx(format!("{:?}", tx)),
+                        Err(err) => TokenAllowancesMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            TokenAllowancesMsg::CheckReceived => {
+                self.received = None;
+                // the owner input shows why it has no address
+                let owner = match self.owner {
+                    Some(owner) => owner,
+                    None => return true
+                };
+                let (token, spender) = match token_and_account(&ctx.props().token_address, &self.wallet) {
+                    Ok(addresses) => addresses,
+                    Err(_) => return true
+                };
+                let chain_id = self.wallet.network().chain_id;
+                let provider = self.wallet.provider.clone();
+                ctx.link().send_future(async move {
+                    let client = match provider.get().await {
+                        Ok(client) => client,
+                        Err(err) => return TokenAllowancesMsg::SetError(err)
+                    };
+                    match fetch_allowances(client, token, owner, &[spender]).await {
+                        Ok(allowances) => TokenAllowancesMsg::SetReceived(owner, spender, chain_id, allowances[0]),
+                        Err(err) => TokenAllowancesMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            TokenAllowancesMsg::SetReceived(owner, spender, chain_id, allowance) => {
+                // another owner was typed, or the account or the network changed while fetching
+                if self.owner != Some(owner) || !self.is_current(ctx, spender, chain_id) {
+                    return false
+                }
+                self.error = None;
+                self.received = Some((owner, allowance));
+                true
+            }
+            TokenAllowancesMsg::TransferFrom => {
+                self.error = None;
+                let inputs = (
+                    token_and_account(&ctx.props().token_address, &self.wallet),
+                    self.owner.ok_or_else(|| DappError::MissingAddress("owner".to_string())),
+                    self.recipient.ok_or_else(|| DappError::MissingAddress("recipient".to_string())),
+                    read_amount(&self.transfer_amount, ctx.props().decimals),
+                );
+                let ((token, from), owner, recipient, amount) = match inputs {
+                    (Ok(accounts), Ok(owner), Ok(recipient), Ok(amount)) => (accounts, owner, recipient, amount),
+                    (Err(err), _, _, _) | (_, Err(err), _, _) | (_, _, Err(err), _) | (_, _, _, Err(err)) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                let chain_id = self.wallet.network().chain_id;
+                ctx.link().send_future(async move {
+                    let client = Arc::new(Eip1193::provider());
+                    if let Err(err) = ensure_chain(&client, chain_id).await {
+                        return TokenAllowancesMsg::SetError(err)
+                    }
+                    match transfer_from_erc20(client, token, from, owner, recipient, amount).await {
+                        Ok(tx) => TokenAllowancesMsg::SetTx(format!("{:?}", tx)),
+                        Err(err) => TokenAllowancesMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            TokenAllowancesMsg::SetTx(hash) => {
+                self.tx = Some(hash);
+                true
+            }
+            TokenAllowancesMsg::TxDone(_) => {
+                ctx.link().send_message(TokenAllowancesMsg::FetchAllowances);
+                if self.received.is_some() {
+                    ctx.link().send_message(TokenAllowancesMsg::CheckReceived);
+                }
+                false
+            }
+            TokenAllowancesMsg::SetError(err) => {
+                log::error!("{:?}", err);
+                self.error = Some(err);
+                true
+            }
+        }
+    }
+
+    // html! checks the AddressInput and TxCard props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let symbol = &ctx.props().symbol;
+        html! {
+            <div>
+                <p>{"Allowances"}</p>
+                <AddressInput placeholder="Spender" on_change={link.callback(TokenAllowancesMsg::SetSpender)} />
+                <button onclick={link.callback(|_| TokenAllowancesMsg::AddSpender)}>
+                    {"Add spender"}
+                </button>
+                if !self.spenders.is_empty() {
+                    <input type="number" placeholder="Amount to approve" ref={&self.approve_amount} />
+                }
+                { for self.spenders.iter().map(|(spender, allowance)| {
+                    let spender = *spender;
+                    html! {
+                        <div key={format!("{:?}", spender)}>
+                            <AddressName address={spender} />{": "}
+                            { match allowance {
+                                Some(allowance) => format!("{} {}", self.format_allowance(*allowance, ctx), symbol),
+                                None => "loading..".to_string(),
+                            } }
+                            <button onclick={link.callback(move |_| TokenAllowancesMsg::Approve(spender, Approval::Exact))}>
+                                {"Approve amount"}
+                            </button>
+                            <button onclick={link.callback(move |_| TokenAllowancesMsg::Approve(spender, Approval::Unlimited))}>
+                                {"Unlimited"}
+                            </button>
+                            <button onclick={link.callback(move |_| TokenAllowancesMsg::Approve(spender, Approval::Revoke))}>
+                                {"Revoke"}
+                            </button>
+                            <button onclick={link.callback(move |_| TokenAllowancesMsg::RemoveSpender(spender))}>
+                                {"x"}
+                            </button>
+                        </div>
+                    }
+                })}
+                <p>{"Spend an allowance (transferFrom)"}</p>
+                <AddressInput placeholder="Owner" on_change={link.callback(TokenAllowancesMsg::SetOwner)} />
+                if let Some((_, allowance)) = self.received {
+                    <span>{ format!(" you can spend {} {}", self.format_allowance(allowance, ctx), symbol) }</span>
+                }
+                <AddressInput placeholder="Recipient" on_change={link.callback(TokenAllowancesMsg::SetRecipient)} />
+                <input type="number" placeholder="Amount" ref={&self.transfer_amount} />
+                <button onclick={link.callback(|_| TokenAllowancesMsg::TransferFrom)}>
+                    {"Transfer from"}
+                </button>
+                if let Some(error) = &self.error {
+                    <p>{ form_error_message(error, "transaction") }</p>
+                }
+                if let Some(hash) = &self.tx {
+                    <TxCard
+                        key={hash.clone()}
+                        hash={hash.clone()}
+                        abi={Some(IERC20_ABI.clone())}
+                        on_done={link.callback(TokenAllowancesMsg::TxDone)}
+                    />
+                }
+            </div>
+        }
+    }
+}
+
+impl TokenAllowances {
+    // account is still the connected one, on chain_id
+    fn is_current(&self, ctx: &Context<Self>, account: Address, chain_id: u64) -> bool {
+        token_and_account(&ctx.props().token_address, &self.wallet).map(|(_, connected)| connected).ok() == Some(account)
+            && self.wallet.network().chain_id == chain_id
+    }
+
+    fn format_allowance(&self, allowance: U256, ctx: &Context<Self>) -> String {
+        if is_unlimited(allowance) {
+            return "unlimited".to_string()
+        }
+        format_units(allowance, ctx.props().decimals).unwrap_or_default()
+    }
+}
@@ -1,54 +1,46 @@
-use yew::prelude::*;
-use wasm_bindgen::prelude::*;
-use ethers::core::{types::{U256, H256}, utils::format_units};
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::{types::{Address, U256}, utils::{format_units, parse_units}};
 use web_sys::HtmlInputElement;
-use crate::{components::tx_card::TxCard};
-
-#[path="../lib.rs"]
-mod lib;
-use lib::{fetch_erc20_information, ERC20Information};
-
-#[path="../helpers.rs"]
-mod helpers;
-use helpers::{decode_hex, wait_receipt};
-
-#[wasm_bindgen(module = "/src/js/metamask.js")]
-extern "C" {
-    #[wasm_bindgen(js_name = "transfer")]
-    #[wasm_bindgen(catch)]
-    pub async fn transfer(
-        token_address: String, 
-        recipient: String, 
-        amount: String, 
-        decimals: i32
-    ) -> Result<JsValue, JsValue>;
-}
-
+use std::sync::Arc;
+use futures::future::{abortable, AbortHandle};
+use crate::{
+    components::{address_input::AddressInput, token_allowances::TokenAllowances, token_form::form_error_message, token_permit::TokenPermit, transfer_history::TransferHistory, tx_card::TxCard},
+    contracts::{load_erc20_information, transfer_erc20, ERC20Information, TokenMetadata, IERC20_ABI},
+    ethereum::{ensure_chain, watch_transfers, DappError, TxStatus},
+    storage::BrowserCache,
+    wallet::{Eip1193, WalletContext},
+};
 
 #[derive(Clone, Debug, PartialEq, Properties)]
 pub struct TokenCardProps {
     pub token_address: String,
-    pub user_address: String,
 }
 
 pub struct TokenCard {
-    symbol: String,
-    decimals: i32,
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    metadata: Option<TokenMetadata>,
+    total_supply: Option<U256>, // read with the balance
     balance: U256,
-    error: Option<String>,
+    block: Option<u64>, // the balance was read at
+    transfer_watcher: Option<AbortHandle>,
+    error: Option<DappError>, // token could not be loaded
+    transfer_error: Option<DappError>,
     tx: Option<String>,
-    tx_processed: Option<bool>,
+    to: Option<Address>, // recipient typed or resolved
     // handle refs for DOM elements
-    to: NodeRef, 
     amount: NodeRef,
 }
 
 pub enum TokenCardMsg {
-    FillERC20(ERC20Information),
-    SetError(String),
+    WalletChanged(WalletContext),
+    Fetch,
+    FillERC20(String, u64, ERC20Information), // account and chain the data belongs to
+    SetError(DappError),
+    SetTransferError(DappError),
     SetTx(String),
-    SetTxProcessed(bool),    
-    EnableListener(JsValue),
+    TxDone(TxStatus),
+    SetRecipient(Option<Address>),
     Transfer,
 }
 
@@ -57,80 +49,127 @@ impl Component for TokenCard {
     type Properties = TokenCardProps;
 
     fn create(ctx: &Context<Self>) -> Self {        
-        let token_address = ctx.props().token_address.clone();
-        let user_address = ctx.props().user_address.clone(); 
-        ctx.link().send_future(async move {
-            match fetch_erc20_information(&token_address, user_address).await {
-                Ok(data) => {
-                    TokenCardMsg::FillERC20(data)
-                },
-                Err(_err) => {
-                    TokenCardMsg::SetError("Error fetching collateral contract".to_string())
-                },
-            }
-        });
-        Self {
-            symbol: String::from("fetching.."),
-            decimals: 18i32,
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(TokenCardMsg::WalletChanged))
+            .expect("TokenCard needs a WalletContext provider");
+        ctx.link().send_message(TokenCardMsg::Fetch);
+        let mut card = Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            metadata: None,
+            total_supply: None,
             balance: U256::from(0),
+            block: None,
+            transfer_watcher: None,
             error: None,
+            transfer_error: None,
             tx: None,
-            tx_processed: None,
-            to: NodeRef::default(),
+            to: None,
             amount: NodeRef::default(),
-        }
+        };
+        card.watch_transfers(ctx);
+        card
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            TokenCardMsg::FillERC20(res) => {
-                self.symbol = res.symbol;
-                self.decimals = res.decimals as i32;
-                self.balance = res.balance;
+            TokenCardMsg::WalletChanged(wallet) => {
+                let account_changed = wallet.address != self.wallet.address
+                    || wallet.provider != self.wallet.provider;
+                self.wallet = wallet;
+                if account_changed {
+                    self.metadata = None;
+                    self.total_supply = None;
+                    self.balance = U256::from(0);
+                    self.block = None;
+                    self.error = None;
+                    self.transfer_error = None;
+                    self.tx = None;
+                    ctx.link().send_message(TokenCardMsg::Fetch);
+                    self.watch_transfers(ctx);
+                }
                 true
-            },
-            TokenCardMsg::Transfer => {
-                let token_address = ctx.props().token_address.clone();
-                let to_address = self.to.cast::<HtmlInputElement>().unwrap().value();
-                let val = self.amount.cast::<HtmlInputElement>()
-                    .unwrap()
-                    .value();
-                let decimals = self.decimals.clone();
+            }
+            TokenCardMsg::Fetch => {
+                let user_address = match self.wallet.address.clone() {
+                    Some(address) => address,
+                    None => return false
+                };
+                let addresses = (
+                    DappError::parse_address(&ctx.props().token_address),
+                    DappError::parse_address(&user_address),
+                );
+                let (token, user) = match addresses {
+                    (Ok(token), Ok(user)) => (token, user),
+                    (Err(err), _) | (_, Err(err)) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                let chain_id = self.wallet.network().chain_id;
+                let provider = self.wallet.provider.clone();
                 ctx.link().send_future(async move {
-                    match transfer(
-                        token_address,                        
-              to_address, 
-                val,
-                        decimals
-                    ).await {
-                        Ok(tx) => TokenCardMsg::EnableListener(tx),
+                    // batched with the other cards loading at the same time
+                    match load_erc20_information(provider, BrowserCache, token, user).await {
+                        Ok(data) => {
+                            TokenCardMsg::FillERC20(user_address, chain_id, data)
+                        },
                         Err(err) => {
-                            log::error!("Error during transfer {:?}", err);
-                            TokenCardMsg::SetError("Error".to_string())
-                        }
+                            TokenCardMsg::SetError(err)
+                        },
                     }
                 });
+                false
+            }
+            TokenCardMsg::FillERC20(user_address, chain_id, res) => {
+                // the account or the network changed while fetching
+                if self.wallet.address.as_ref() != Some(&user_address) || self.wallet.network().chain_id != chain_id {
+                    return false
+                }
+                self.metadata = Some(res.metadata);
+                self.total_supply = res.total_supply;
+                self.balance = res.balance;
+                self.block = Some(res.block);
                 true
+            },
+            TokenCardMsg::SetRecipient(to) => {
+                self.to = to;
+                false
             }
-            TokenCardMsg::EnableListener(tx) => {
-                ctx.link().send_message(TokenCardMsg::SetTx(tx.as_string().unwrap().clone()));
+            TokenCardMsg::Transfer => {
+                self.transfer_error = None;
+                let val = self.amount.cast::<HtmlInputElement>()
+                    .map(|input| input.value())
+                    .unwrap_or_default();
+                let inputs = (
+                    DappError::parse_address(&ctx.props().token_address),
+                    DappError::parse_address(self.wallet.address.as_deref().unwrap_or_default()),
+                    self.to.ok_or_else(|| DappError::MissingAddress("recipient".to_string())),
+                    parse_units(&val, self.decimals())
+                        .map_err(|_| DappError::InvalidInput(format!("\"{}\" is not a valid amount", val))),
+                );
+                let (token, from, to, amount) = match inputs {
+                    (Ok(token), Ok(from), Ok(to), Ok(amount)) => (token, from, to, amount),
+                    (Err(err), _, _, _) | (_, Err(err), _, _) | (_, _, Err(err), _) | (_, _, _, Err(err)) => {
+                        self.transfer_error = Some(err);
+                        return true
+                    }
+                };
+                let chain_id = self.wallet.network().chain_id;
                 ctx.link().send_future(async move {
-                    // JsValue(String) has 66 bytes => 0x+base64
-                    let substring = &tx.as_string().unwrap()[2..]; // splits "0x"
-                    let hash_from_hex = decode_hex(substring).unwrap(); // 64 bytes to 32 bytes
-                    let from_hex_h256 = H256::from_slice(&hash_from_hex); // H256                    
-                    let tx = wait_receipt(from_hex_h256).await; 
-                    match tx.as_ref() {
-                        Ok(receipt) => {
-                            log::info!("Receipt: {:?}", receipt);
-                            TokenCardMsg::SetTxProcessed(true)
-                        },
+                    // sent through the wallet, which estimates gas and signs
+                    let client = Arc::new(Eip1193::provider());
+                    if let Err(err) = ensure_chain(&client, chain_id).await {
+                        return TokenCardMsg::SetTransferError(err)
+                    }
+                    match transfer_erc20(client, token, from, to, amount).await {
+                        Ok(tx) => TokenCardMsg::SetTx(format!("{:?}", tx)),
                         Err(err) => {
-                            log::error!("Error {:?}", err);
-                            TokenCardMsg::SetTxProcessed(false)
+                            log::error!("Error during transfer {:?}", err);
+                            TokenCardMsg::SetTransferError(err)
                         }
                     }
-                }); 
+                });
                 true
             }
             TokenCardMsg::SetTx(hash) => {
@@ -138,56 +177,83 @@ impl Component for TokenCard {
                 self.tx = Some(hash);
                 true
             }
-            TokenCardMsg::SetTxProcessed(status) => {
-                log::info!("Setting tx processed: {:?}", status);
-                self.tx_processed = Some(status);
+            TokenCardMsg::TxDone(status) => {
+                // the balance moved
+                if let TxStatus::Confirmed(_) = status {
+                    ctx.link().send_message(TokenCardMsg::Fetch);
+                }
+                false
+            }
+            TokenCardMsg::SetError(error) => {
+                self.error = Some(error);
                 true
             }
-            TokenCardMsg::SetError(error_msg) => {
-                self.error = Some(error_msg);
+            TokenCardMsg::SetTransferError(error) => {
+                self.transfer_error = Some(error);
                 true
             }
         }        
     }
+    // html! checks the AddressInput and TxCard props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let Self { symbol, decimals, balance, error, to:_, amount:_, tx:_, tx_processed:_} = self;
+        let Self { wallet, _wallet_handle:_, metadata, total_supply, balance, block, transfer_watcher:_, error, transfer_error, to:_, amount:_, tx:_} = self;
+        let symbol = metadata.as_ref().map_or("fetching..", |metadata| &metadata.symbol).to_string();
+        let decimals = self.decimals();
         html! {
             <div>
-                if let Some(error_msg) = error {
+                if let Some(error) = error {
                     <div>
-                        { error_msg }
+                        { error.to_string() }
                     </div>
                 } else {
                     <a                        
-                        href={format!("https://rinkeby.etherscan.io/token/{}",ctx.props().token_address.clone())} 
+                        href={wallet.network().explorer_token(&ctx.props().token_address)} 
                         target="_blank"
                     >
                         {"Token: "}
-                        {"r"}{ symbol }
+                        { &symbol }
                     </a>
+                    if let Some(metadata) = metadata {
+                        <p>
+                            { &metadata.name }
+                            if let Some(total_supply) = total_supply {
+                                {format!(", total supply {} {}", format_units(*total_supply, decimals).unwrap_or_default(), symbol)}
+                            }
+                            if metadata.decimals.is_none() {
+                                <small>{" (no decimals, amounts in base units)"}</small>
+                            }
+                        </p>
+                    }
                     <p>
                         {"Your balance: "}
-                        {format_units(balance, *decimals).unwrap()}
+                        {format_units(balance, decimals).unwrap_or_default()}
+                        if let Some(block) = block {
+                            <small>{format!(" (last updated at block {})", block)}</small>
+                        }
                         <br />
                     </p>
                     <div>
                         <p>{"Transfer function"}</p>
-                        <input
-                            type="text"
+                        <AddressInput
                             placeholder="Recipient"
-                            ref={&self.to}
+                            on_change={ctx.link().callback(TokenCardMsg::SetRecipient)}
                         />
                         <input
                             type="number"
                             placeholder="Amount"
                             ref={&self.amount}
                         />
+                        if let Some(error) = transfer_error {
+                            <p>{ form_error_message(error, "transfer") }</p>
+                        }
                         {
                             match &self.tx {
                                 Some(hash) => html! {
                                     <TxCard
                                         hash = {hash.to_string()}
-                                        status = {self.tx_processed}
+                                        abi = {Some(IERC20_ABI.clone())}
+                                        on_done = {ctx.link().callback(TokenCardMsg::TxDone)}
                                     />        
                                 },                                
                                 None => html! {
@@ -198,8 +264,62 @@ impl Component for TokenCard {
                             }
                         }
                     </div>
+                    <TokenAllowances
+                        token_address = {ctx.props().token_address.clone()}
+                        symbol = {symbol.clone()}
+                        decimals = {decimals}
+                    />
+                    <TokenPermit
+                        token_address = {ctx.props().token_address.clone()}
+                        symbol = {symbol.clone()}
+                        decimals = {decimals}
+                    />
+                    <TransferHistory
+                        token_address = {ctx.props().token_address.clone()}
+                        symbol = {symbol.clone()}
+                        decimals = {decimals}
+                    />
                 }
             </div>
         }
     }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        if let Some(watcher) = self.transfer_watcher.take() {
+            watcher.abort();
+        }
+    }
+}
+
+impl TokenCard {
+    fn decimals(&self) -> i32 {
+        self.metadata.as_ref().map_or(18, TokenMetadata::display_decimals)
+    }
+
+    // refreshes the balance whenever a Transfer event involves the account,
+    // stops the previous watcher (account or network changed)
+    fn watch_transfers(&mut self, ctx: &Context<Self>) {
+        if let Some(watcher) = self.transfer_watcher.take() {
+            watcher.abort();
+        }
+        let addresses = (
+            DappError::parse_address(&ctx.props().token_address),
+            DappError::parse_address(self.wallet.address.as_deref().unwrap_or_default()),
+        );
+        let (token, account) = match addresses {
+            (Ok(token), Ok(account)) => (token, account),
+            _ => return
+        };
+        let on_transfer = ctx.link().callback(|_: u64| TokenCardMsg::Fetch);
+        let (watch, watcher) = abortable(watch_transfers(
+            self.wallet.provider.clone(),
+            token,
+            account,
+            move |block| on_transfer.emit(block),
+        ));
+        wasm_bindgen_futures::spawn_local(async move {
+            let _ = watch.await;
+        });
+        self.transfer_watcher = Some(watcher);
+    }
 }
@@ -0,0 +1,34 @@
+use yew::prelude::*;
+use ethers::core::{types::{Address, U256}, utils::parse_units};
+use web_sys::HtmlInputElement;
+use crate::{ethereum::DappError, wallet::WalletContext};
+
+// Inputs and errors of the forms acting for the connected account (transfers,
+// allowances, permits).
+
+pub fn token_and_account(token_address: &str, wallet: &WalletContext) -> Result<(Address, Address), DappError> {
+    Ok((
+        DappError::parse_address(token_address)?,
+        DappError::parse_address(wallet.address.as_deref().unwrap_or_default())?,
+    ))
+}
+
+pub fn input_value(input: &NodeRef) -> String {
+    input.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default()
+}
+
+// amount typed in token units
+pub fn read_amount(input: &NodeRef, decimals: i32) -> Result<U256, DappError> {
+    let value = input_value(input);
+    parse_units(value.trim(), decimals)
+        .map_err(|_| DappError::InvalidInput(format!("\"{}\" is not a valid amount", value)))
+}
+
+// action is what the wallet was asked to sign or send
+pub fn form_error_message(error: &DappError, action: &str) -> String {
+    match error {
+        DappError::WalletRejected => "Cancelled in the wallet".to_string(),
+        DappError::ContractRevert(reason) => format!("The {} would fail: {}", action, reason),
+        error => error.to_string(),
+    }
+}
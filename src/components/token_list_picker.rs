@@ -0,0 +1,177 @@
+use yew::{prelude::*, context::ContextHandle};
+use web_sys::HtmlInputElement;
+use wasm_bindgen_futures::JsFuture;
+use std::collections::HashSet;
+use crate::{
+    contracts::{bundled_token_list, parse_token_list, TokenInfo, TokenList},
+    ethereum::DappError,
+    helpers::short_address,
+    wallet::WalletContext,
+};
+
+// matches shown at once, refine the search for the others
+const MAX_RESULTS: usize = 20;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct TokenListPickerProps {
+    pub on_select: Callback<String>, // address of the picked token
+}
+
+pub struct TokenListPicker {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    lists: Vec<TokenList>,
+    query: String,
+    error: Option<DappError>,
+    // handle refs for DOM elements
+    search: NodeRef,
+    upload: NodeRef,
+}
+
+pub enum TokenListPickerMsg {
+    WalletChanged(WalletContext),
+    Search,
+    Upload,
+    Loaded(TokenList),
+    SetError(DappError),
+}
+
+impl Component for TokenListPicker {
+    type Message = TokenListPickerMsg;
+    type Properties = TokenListPickerProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(TokenListPickerMsg::WalletChanged))
+            .expect("TokenListPicker needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            lists: vec![bundled_token_list()],
+            query: String::new(),
+            error: None,
+            search: NodeRef::default(),
+            upload: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TokenListPickerMsg::WalletChanged(wallet) => {
+                let changed = wallet.provider != self.wallet.provider;
+                self.wallet = wallet;
+                changed
+            }
+            TokenListPickerMsg::Search => {
+                self.query = self.search.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+                true
+            }
+            TokenListPickerMsg::Upload => {
+                let file = match self.upload.cast::<HtmlInputElement>()
+                    .and_then(|input| input.files())
+                    .and_then(|files| files.get(0))
+                {
+                    Some(file) => file,
+                    None => return false
+                };
+                ctx.link().send_future(async move {
+                    let text = match JsFuture::from(file.text()).await {
+                        Ok(text) => text.as_string().unwrap_or_default(),
+                        Err(_) => return TokenListPickerMsg::SetError(
+                            DappError::InvalidTokenList(format!("could not read {}", file.name()))
+                        ),
+                    };
+                    match parse_token_list(&text) {
+                        Ok(list) => TokenListPickerMsg::Loaded(list),
+                        Err(err) => TokenListPickerMsg::SetError(err),
+                    }
+                });
+                false
+            }
+            TokenListPickerMsg::Loaded(list) => {
+                // a new version of a list already loaded replaces it
+                self.lists.retain(|loaded| loaded.name != list.name);
+                self.lists.push(list);
+                self.error = None;
+                true
+            }
+            TokenListPickerMsg::SetError(err) => {
+                self.error = Some(err);
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let chain_id = self.wallet.network().chain_id;
+        let results = self.search_results(chain_id);
+        html! {
+            <div>
+                <h3>{"Token lists"}</h3>
+                <ul>
+                    { for self.lists.iter().map(|list| html! {
+                        <li>
+                            {format!("{} {}: {} tokens on {}", list.name, list.version_label(), list.tokens_on(chain_id).count(), self.wallet.network().name)}
+                        </li>
+                    })}
+                </ul>
+                <label>
+                    {"Load a token list "}
+                    <input
+                        type="file"
+                        accept=".json,application/json"
+                        ref={&self.upload}
+                        onchange={link.callback(|_| TokenListPickerMsg::Upload)}
+                    />
+                </label>
+                if let Some(err) = &self.error {
+                    <p>{err.to_string()}</p>
+                }
+                <div>
+                    <input
+                        type="text"
+                        ref={&self.search}
+                        placeholder="Search by symbol or name"
+                        oninput={link.callback(|_| TokenListPickerMsg::Search)}
+                    />
+                </div>
+                if results.is_empty() {
+                    <p>{"No token found on this network"}</p>
+                }
+                <ul>
+                    { for results.iter().take(MAX_RESULTS).map(|token| {
+                        let address = token.address.clone();
+                        let on_select = ctx.props().on_select.clone();
+                        html! {
+                            <li key={token.address.clone()}>
+                                if let Some(logo) = &token.logo_uri {
+                                    <img src={logo.clone()} alt={token.symbol.clone()} width="16" height="16" />
+                                }
+                                {format!(" {} ({}) {} ", token.symbol, token.name, short_address(&token.address))}
+                                <button onclick={Callback::from(move |_| on_select.emit(address.clone()))}>
+                                    {"Add"}
+                                </button>
+                            </li>
+                        }
+                    })}
+                </ul>
+                if results.len() > MAX_RESULTS {
+                    <p>{format!("{} more, refine the search", results.len() - MAX_RESULTS)}</p>
+                }
+            </div>
+        }
+    }
+}
+
+impl TokenListPicker {
+    // tokens of every list on the current chain, each address once
+    fn search_results(&self, chain_id: u64) -> Vec<&TokenInfo> {
+        let mut seen = HashSet::new();
+        self.lists.iter()
+            .flat_map(|list| list.tokens_on(chain_id))
+            .filter(|token| token.matches(&self.query))
+            .filter(|token| seen.insert(token.address.to_lowercase()))
+            .collect()
+    }
+}
@@ -0,0 +1,322 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::{types::{Address, Signature, U256}, utils::format_units};
+use std::sync::Arc;
+use crate::{
+    components::{address_input::AddressInput, address_name::AddressName, token_form::{form_error_message, input_value, read_amount, token_and_account}, tx_card::TxCard},
+    contracts::{fetch_permit_support, permit_typed_data, submit_permit, IERC20PERMIT_ABI, Permit, PermitSignature, PermitSupport},
+    ethereum::{ensure_chain, recover_typed_data_signer, DappError, TxStatus, TypedData},
+    wallet::{now, sign_typed_data, Eip1193, WalletContext},
+};
+
+// validity of a permit when the deadline field is left empty
+const DEFAULT_DEADLINE_MINUTES: u64 = 60;
+
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct TokenPermitProps {
+    pub token_address: String,
+    pub symbol: String,
+    pub decimals: i32,
+}
+
+pub struct TokenPermit {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    // detected when the panel is first opened, None until then
+    open: bool,
+    support: Option<Result<PermitSupport, DappError>>,
+    signing: bool,
+    signed: Option<(Permit, PermitSignature)>,
+    error: Option<DappError>,
+    tx: Option<String>,
+    spender: Option<Address>, // typed or resolved
+    // handle refs for DOM elements
+    amount: NodeRef,
+    deadline: NodeRef,
+}
+
+pub enum TokenPermitMsg {
+    WalletChanged(WalletContext),
+    Toggle,
+    Detect,
+    SetSpender(Option<Address>),
+    SetSupport(Address, u64, Result<PermitSupport, DappError>), // owner and chain of the nonce and domain
+    Sign,
+    Signed(Permit, TypedData, Signature),
+    Submit,
+    SetTx(String),
+    TxDone(TxStatus),
+    SetError(DappError),
+}
+
+impl Component for TokenPermit {
+    type Message = TokenPermitMsg;
+    type Properties = TokenPermitProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(TokenPermitMsg::WalletChanged))
+            .expect("TokenPermit needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            open: false,
+            support: None,
+            signing: false,
+            signed: None,
+            error: None,
+            tx: None,
+            spender: None,
+            amount: NodeRef::default(),
+            deadline: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TokenPermitMsg::WalletChanged(wallet) => {
+                let account_changed = wallet.address != self.wallet.address
+                    || wallet.provider != self.wallet.provider;
+                self.wallet = wallet;
+                if account_changed {
+                    // signed by the previous account or for the previous chain
+                    self.signed = None;
+                    self.error = None;
+                    self.tx = None;
+                    self.support = None;
+                    if self.open {
+                        ctx.link().send_message(TokenPermitMsg::Detect);
+                    }
+                }
+                account_changed
+            }
+            TokenPermitMsg::Toggle => {
+                self.open = !self.open;
+                if self.open && self.support.is_none() {
+                    ctx.link().send_message(TokenPermitMsg::Detect);
+                }
+                true
+            }
+            TokenPermitMsg::Detect => {
+                let (token, owner) = match token_and_account(&ctx.props().token_address, &self.wallet) {
+                    Ok(addresses) => addresses,
+                    Err(_) => return false
+                };
+                self.support = None;
+                let chain_id = self.wallet.network().chain_id;
+                let provider = self.wallet.provider.clone();
+                ctx.link().send_future(async move {
+                    let support = match provider.get().await {
+                        Ok(client) => fetch_permit_support(client, token, owner).await,
+                        Err(err) => Err(err),
+                    };
+                    TokenPermitMsg::SetSupport(owner, chain_id, support)
+                });
+                true
+            }
+            TokenPermitMsg::SetSpender(spender) => {
+                self.spender = spender;
+                false
+            }
+            TokenPermitMsg::SetSupport(owner, chain_id, support) => {
+                // the account or the network changed while detecting, the
+                // domain would sign for the other chain
+                let account = token_and_account(&ctx.props().token_address, &self.wallet).map(|(_, account)| account).ok();
+                if account != Some(owner) || self.wallet.network().chain_id != chain_id {
+                    return false
+                }
+                self.support = Some(support);
+                true
+            }
+            TokenPermitMsg::Sign => {
+                self.error = None;
+                self.signed = None;
+                let support = match &self.support {
+                    Some(Ok(support)) => support.clone(),
+                    _ => return false
+                };
+                let inputs = (
+                    token_and_account(&ctx.props().token_address, &self.wallet),
+                    self.spender.ok_or_else(|| DappError::MissingAddress("spender".to_string())),
+                    read_amount(&self.amount, ctx.props().decimals),
+                    self.read_deadline(),
+                );
+                let ((_, owner), spender, value, deadline) = match inputs {
+                    (Ok(accounts), Ok(spender), Ok(value), Ok(deadline)) => (accounts, spender, value, deadline),
+                    (Err(err), _, _, _) | (_, Err(err), _, _) | (_, _, Err(err), _) | (_, _, _, Err(err)) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                let permit = Permit { owner, spender, value, nonce: support.nonce, deadline };
+                let (payload, typed_data) = match permit_typed_data(&support.domain, &permit) {
+                    Ok(typed_data) => typed_data,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                self.signing = true;
+                let chain_id = self.wallet.network().chain_id;
+                ctx.link().send_future(async move {
+                    // the domain is bound to the chain, the wallet refuses to sign on another one
+                    if let Err(err) = ensure_chain(&Eip1193::provider(), chain_id).await {
+                        return TokenPermitMsg::SetError(err)
+                    }
+                    match sign_typed_data(&payload, owner).await {
+                        Ok(signature) => TokenPermitMsg::Signed(permit, typed_data, signature),
+                        Err(err) => TokenPermitMsg::SetError(err),
+                    }
+                });
+                true
+            }
+            TokenPermitMsg::Signed(permit, typed_data, signature) => {
+                self.signing = false;
+                match recover_typed_data_signer(&typed_data, &signature) {
+                    Ok(signer) if signer == permit.owner => {
+                        self.signed = Some((permit, signature.into()));
+                    }
+                    Ok(signer) => {
+                        self.error = Some(DappError::InvalidSignature(format!("signed by {:?}, not {:?}", signer, permit.owner)));
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+                true
+            }
+            TokenPermitMsg::Submit => {
+                let (permit, signature) = match &self.signed {
+                    Some((permit, signature)) => (permit.clone(), *signature),
+                    None => return false
+                };
+                let (token, from) = match token_and_account(&ctx.props().token_address, &self.wallet) {
+                    Ok(addresses) => addresses,
+                    Err(err) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                self.error = None;
+                let chain_id = self.wallet.network().chain_id;
+                ctx.link().send_future(async move {
+                    let client = Arc::new(Eip1193::provider());
+                    if let Err(err) = ensure_chain(&client, chain_id).await {
+                        return TokenPermitMsg::SetError(err)
+                    }
+                    match submit_permit(client, token, from, &permit, signature).await {
+                        Ok(tx) => TokenPermitMsg::SetTx(format!("{:?}", tx)),
+                        Err(err) => TokenPermitMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            TokenPermitMsg::SetTx(hash) => {
+                self.tx = Some(hash);
+                true
+            }
+            TokenPermitMsg::TxDone(_) => {
+                // the nonce was used, the signature can't be submitted again
+                self.signed = None;
+                ctx.link().send_message(TokenPermitMsg::Detect);
+                true
+            }
+            TokenPermitMsg::SetError(err) => {
+                log::error!("{:?}", err);
+                self.signing = false;
+                self.error = Some(err);
+                true
+            }
+        }
+    }
+
+    // html! checks the TxCard props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        html! {
+            <div>
+                <button onclick={link.callback(|_| TokenPermitMsg::Toggle)}>
+                    { if self.open { "Hide permit" } else { "Permit (EIP-2612)" } }
+                </button>
+                if self.open {
+                    { self.view_support(ctx) }
+                }
+                if let Some((permit, signature)) = &self.signed {
+                    <div>
+                        <p>
+                            { format!(
+                                "{} {} for ",
+                                format_units(permit.value, ctx.props().decimals).unwrap_or_default(),
+                                ctx.props().symbol,
+                            ) }
+                            <AddressName address={permit.spender} />
+                        </p>
+                        <table>
+                            <tr><td>{"v"}</td><td><code>{signature.v}</code></td></tr>
+                            <tr><td>{"r"}</td><td><code>{format!("{:?}", signature.r)}</code></td></tr>
+                            <tr><td>{"s"}</td><td><code>{format!("{:?}", signature.s)}</code></td></tr>
+                            <tr><td>{"deadline"}</td><td><code>{permit.deadline.to_string()}</code></td></tr>
+                        </table>
+                        <button onclick={link.callback(|_| TokenPermitMsg::Submit)}>
+                            {"Submit permit"}
+                        </button>
+                    </div>
+                }
+                if let Some(error) = &self.error {
+                    <p>{ form_error_message(error, "permit") }</p>
+                }
+                if let Some(hash) = &self.tx {
+                    <TxCard
+                        key={hash.clone()}
+                        hash={hash.clone()}
+                        abi={Some(IERC20PERMIT_ABI.clone())}
+                        on_done={link.callback(TokenPermitMsg::TxDone)}
+                    />
+                }
+            </div>
+        }
+    }
+}
+
+impl TokenPermit {
+    // html! checks the AddressInput props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
+    fn view_support(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        match &self.support {
+            None => html! { <p>{"Checking permit support.."}</p> },
+            Some(Err(err)) => html! { <p>{err.to_string()}</p> },
+            Some(Ok(support)) => html! {
+                <div>
+                    <small>{ format!(
+                        "Domain {} version {}, nonce {}",
+                        support.domain.name.as_deref().unwrap_or_default(),
+                        support.domain.version.as_deref().unwrap_or("-"),
+                        support.nonce,
+                    ) }</small>
+                    <div>
+                        <AddressInput placeholder="Spender" on_change={link.callback(TokenPermitMsg::SetSpender)} />
+                        <input type="number" placeholder="Amount" ref={&self.amount} />
+                        <input
+                            type="number"
+                            placeholder={format!("Valid for minutes ({})", DEFAULT_DEADLINE_MINUTES)}
+                            ref={&self.deadline}
+                        />
+                        <button disabled={self.signing} onclick={link.callback(|_| TokenPermitMsg::Sign)}>
+                            { if self.signing { "Waiting for the wallet.." } else { "Sign permit" } }
+                        </button>
+                    </div>
+                </div>
+            },
+        }
+    }
+
+    // unix time in seconds, now plus the minutes typed
+    fn read_deadline(&self) -> Result<U256, DappError> {
+        let value = input_value(&self.deadline);
+        let minutes = match value.trim() {
+            "" => DEFAULT_DEADLINE_MINUTES,
+            minutes => minutes.parse::<u64>()
+                .map_err(|_| DappError::InvalidInput(format!("\"{}\" is not a number of minutes", value)))?,
+        };
+        Ok(U256::from(now() as u64 / 1000) + U256::from(minutes) * 60)
+    }
+}
@@ -0,0 +1,197 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::utils::format_units;
+use wasm_bindgen::JsValue;
+use crate::{
+    components::address_name::AddressName,
+    contracts::{fetch_transfers, TokenTransfer, TransferDirection},
+    ethereum::{block_range, DappError},
+    helpers::short_address,
+    wallet::WalletContext,
+};
+
+// blocks scanned by each "load" click
+const HISTORY_BLOCKS: u64 = 20_000;
+
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct TransferHistoryProps {
+    pub token_address: String,
+    pub symbol: String,
+    pub decimals: i32,
+}
+
+pub struct TransferHistory {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    transfers: Vec<TokenTransfer>,
+    oldest_block: Option<u64>, // first block scanned so far
+    loading: bool,
+    scanned_to: Option<u64>,
+    error: Option<DappError>,
+}
+
+pub enum TransferHistoryMsg {
+    WalletChanged(WalletContext),
+    Load, // the previous HISTORY_BLOCKS before what is already listed
+    Progress(u64),
+    SetTransfers(String, u64, u64, Vec<TokenTransfer>), // account, chain and first block of the range
+    SetError(DappError),
+}
+
+impl Component for TransferHistory {
+    type Message = TransferHistoryMsg;
+    type Properties = TransferHistoryProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(TransferHistoryMsg::WalletChanged))
+            .expect("TransferHistory needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            transfers: Vec::new(),
+            oldest_block: None,
+            loading: false,
+            scanned_to: None,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TransferHistoryMsg::WalletChanged(wallet) => {
+                if wallet.address != self.wallet.address || wallet.provider != self.wallet.provider {
+                    self.transfers.clear();
+                    self.oldest_block = None;
+                    self.loading = false;
+                    self.error = None;
+                }
+                self.wallet = wallet;
+                true
+            }
+            TransferHistoryMsg::Load => {
+                self.error = None;
+                let inputs = (
+                    DappError::parse_address(&ctx.props().token_address),
+                    DappError::parse_address(self.wallet.address.as_deref().unwrap_or_default()),
+                );
+                let (token, account) = match inputs {
+                    (Ok(token), Ok(account)) => (token, account),
+                    (Err(err), _) | (_, Err(err)) => {
+                        self.error = Some(err);
+                        return true
+                    }
+                };
+                let to_block = match self.oldest_block {
+                    Some(0) => return false,
+                    Some(block) => Some(block - 1),
+                    None => None,
+                };
+                self.loading = true;
+                self.scanned_to = None;
+                let user_address = self.wallet.address.clone().unwrap_or_default();
+                let chain_id = self.wallet.network().chain_id;
+                let provider = self.wallet.provider.clone();
+                let on_progress = ctx.link().callback(TransferHistoryMsg::Progress);
+                ctx.link().send_future(async move {
+                    let client = match provider.get().await {
+                        Ok(client) => client,
+                        Err(err) => return TransferHistoryMsg::SetError(err)
+                    };
+                    let (from_block, to_block) = match block_range(&client, None, to_block, HISTORY_BLOCKS).await {
+                        Ok(range) => range,
+                        Err(err) => return TransferHistoryMsg::SetError(err)
+                    };
+                    match fetch_transfers(client, token, account, from_block, to_block, move |block| on_progress.emit(block)).await {
+                        Ok(transfers) => TransferHistoryMsg::SetTransfers(user_address, chain_id, from_block, transfers),
+                        Err(err) => TransferHistoryMsg::SetError(err)
+                    }
+                });
+                true
+            }
+            TransferHistoryMsg::Progress(block) => {
+                self.scanned_to = Some(block);
+                true
+            }
+            TransferHistoryMsg::SetTransfers(user_address, chain_id, from_block, mut transfers) => {
+                // the account or the network changed while fetching
+                if self.wallet.address.as_ref() != Some(&user_address) || self.wallet.network().chain_id != chain_id {
+                    return false
+                }
+                self.loading = false;
+                self.oldest_block = Some(from_block);
+                self.transfers.append(&mut transfers);
+                true
+            }
+            TransferHistoryMsg::SetError(err) => {
+                log::error!("{:?}", err);
+                self.loading = false;
+                self.error = Some(err);
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        html! {
+            <div>
+                <p>{"Transfer history"}</p>
+                if !self.transfers.is_empty() {
+                    <table>
+                        { for self.transfers.iter().map(|transfer| self.view_transfer(ctx, transfer)) }
+                    </table>
+                } else if self.oldest_block.is_some() {
+                    <p>{"No transfers found"}</p>
+                }
+                if let Some(block) = self.oldest_block {
+                    <p>{ format!("Scanned since block {}", block) }</p>
+                }
+                if self.loading {
+                    <p>{ format!("Loading.. {}", self.scanned_to.map(|block| format!("block {}", block)).unwrap_or_default()) }</p>
+                } else if self.oldest_block != Some(0) {
+                    <button onclick={link.callback(|_| TransferHistoryMsg::Load)}>
+                        { if self.oldest_block.is_none() { "Load history" } else { "Load older" } }
+                    </button>
+                }
+                if let Some(error) = &self.error {
+                    <p>{ error.to_string() }</p>
+                }
+            </div>
+        }
+    }
+}
+
+impl TransferHistory {
+    fn view_transfer(&self, ctx: &Context<Self>, transfer: &TokenTransfer) -> Html {
+        let props = ctx.props();
+        let hash = format!("{:?}", transfer.tx_hash);
+        let (direction, sign) = match transfer.direction {
+            TransferDirection::In => ("from", "+"),
+            TransferDirection::Out => ("to", "-"),
+            TransferDirection::ToSelf => ("to", ""),
+        };
+        html! {
+            <tr key={format!("{}-{}", hash, transfer.log_index)}>
+                <td>{ transfer.timestamp.map(format_timestamp).unwrap_or_else(|| format!("block {}", transfer.block)) }</td>
+                <td>{ direction }{" "}<AddressName address={transfer.counterparty} /></td>
+                <td>
+                    { sign }{ format_units(transfer.amount, props.decimals).unwrap_or_default() }
+                    {" "}{ &props.symbol }
+                </td>
+                <td>
+                    if let Some(url) = self.wallet.network().explorer_tx(&hash) {
+                        <a href={url} target="_blank">{ short_address(&hash) }</a>
+                    } else {
+                        { short_address(&hash) }
+                    }
+                </td>
+            </tr>
+        }
+    }
+}
+
+// local date and time of the browser
+fn format_timestamp(timestamp: u64) -> String {
+    let date = js_sys::Date::new(&JsValue::from_f64(timestamp as f64 * 1000.0));
+    String::from(date.to_locale_string("default", &JsValue::UNDEFINED))
+}
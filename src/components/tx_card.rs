@@ -1,38 +1,163 @@
 use yew::prelude::*;
-
-#[path="../helpers.rs"]
-mod helpers;
-use helpers::short_address;
+use ethers::{abi::Abi, core::{types::{TransactionReceipt, H256}, utils::format_units}};
+use futures::future::{abortable, AbortHandle};
+use crate::{
+    ethereum::{replay_revert, watch_transaction, DappError, ProviderHandle, RevertReason, TxStatus, WatchOptions},
+    helpers::short_address,
+    wallet::WalletContext,
+};
 
 #[derive(Properties, PartialEq)]
 pub struct TxProps {
     pub hash: String,
-    pub status: Option<bool>,
+    #[prop_or_default]
+    pub options: WatchOptions,
+    #[prop_or_default]
+    pub abi: Option<Abi>, // of the called contract, to decode its custom errors
+    #[prop_or_default]
+    pub on_done: Callback<TxStatus>, // final status, once known
+}
+
+pub struct TxCard {
+    provider: ProviderHandle, // where the transaction was sent, even if the user switches later
+    status: TxStatus,
+    revert: Option<Result<Option<RevertReason>, DappError>>,
+    watcher: Option<AbortHandle>,
+}
+
+pub enum TxCardMsg {
+    Progress(TxStatus),
+    SetRevert(Result<Option<RevertReason>, DappError>),
+}
+
+impl Component for TxCard {
+    type Message = TxCardMsg;
+    type Properties = TxProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, _) = ctx.link()
+            .context::<WalletContext>(Callback::noop())
+            .expect("TxCard needs a WalletContext provider");
+        let mut watcher = None;
+        match ctx.props().hash.parse::<H256>() {
+            Ok(tx_hash) => {
+                let on_progress = ctx.link().callback(TxCardMsg::Progress);
+                let (watch, handle) = abortable(watch_transaction(
+                    wallet.provider.clone(),
+                    tx_hash,
+                    ctx.props().options.clone(),
+                    move |status| on_progress.emit(status),
+                ));
+                wasm_bindgen_futures::spawn_local(async move {
+                    let _ = watch.await;
+                });
+                watcher = Some(handle);
+            }
+            Err(err) => log::error!("Not a transaction hash {:?}: {}", ctx.props().hash, err),
+        }
+        Self {
+            provider: wallet.provider,
+            status: TxStatus::Pending,
+            revert: None,
+            watcher,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TxCardMsg::Progress(status) => {
+                log::info!("Tx {} {:?}", ctx.props().hash, status);
+                if status.is_final() {
+                    ctx.props().on_done.emit(status.clone());
+                }
+                if let TxStatus::Failed(receipt) = &status {
+                    let receipt = receipt.clone();
+                    let provider = self.provider.clone();
+                    let abi = ctx.props().abi.clone();
+                    ctx.link().send_future(async move {
+                        let reason = match provider.get().await {
+                            Ok(client) => replay_revert(&client, &receipt, abi.as_ref()).await,
+                            Err(err) => Err(err),
+                        };
+                        TxCardMsg::SetRevert(reason)
+                    });
+                }
+                self.status = status;
+                true
+            }
+            TxCardMsg::SetRevert(reason) => {
+                self.revert = Some(reason);
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        html! {
+            <div>
+                <header>
+                    <a
+                        href={self.provider.network().explorer_tx(&props.hash)}
+                        target="_blank"
+                    >
+                        {"Tx: "}{short_address(&props.hash)}
+                    </a>
+                    {" "}
+                    <span>{ self.status_message(ctx) }</span>
+                </header>
+                if let TxStatus::Confirmed(receipt) | TxStatus::Failed(receipt) = &self.status {
+                    <p>{ gas_message(receipt) }</p>
+                }
+            </div>
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
 }
 
-#[function_component(TxCard)]
-pub fn tx_box(props: &TxProps) -> Html {
-    html! {
-        <div>
-            <header>
-                <a                        
-                    href={format!("https://rinkeby.etherscan.io/tx/{}",props.hash.clone())} 
-                    target="_blank"
-                >
-
-                    {"Tx: "}{short_address(&props.hash)}
-                </a>
-
-                if let Some(status) = props.status {
-                    if status {
-                        <span>{"Success"}</span>
-                    } else {
-                        <span>{"Error"}</span>
-                    }                    
-                } else {
-                    <span>{"Awaiting confirmation"}</span>
+impl TxCard {
+    fn status_message(&self, ctx: &Context<Self>) -> String {
+        let options = &ctx.props().options;
+        match &self.status {
+            TxStatus::Pending => "Pending".to_string(),
+            TxStatus::Mined { block, confirmations } => format!(
+                "Mined in block {}, {}/{} confirmations", block, confirmations, options.confirmations
+            ),
+            TxStatus::Confirmed(receipt) => format!("Confirmed in block {}", receipt.block_number.unwrap_or_default()),
+            TxStatus::Failed(receipt) => {
+                let block = receipt.block_number.unwrap_or_default();
+                match &self.revert {
+                    None => format!("Failed in block {}, looking for the reason..", block),
+                    Some(Ok(Some(reason))) => format!("Failed in block {}: {}", block, reason),
+                    // the replay succeeds when the state changed since
+                    Some(Ok(None)) => format!(
+                        "Failed in block {} (could not determine the revert reason, the state changed since)", block
+                    ),
+                    Some(Err(err)) => format!("Failed in block {} (reason unavailable: {})", block, err),
                 }
-            </header>
-        </div>        
+            }
+            TxStatus::Dropped => "Dropped by the network, it will not be mined".to_string(),
+            TxStatus::Replaced => "Replaced by another transaction from the same account".to_string(),
+            TxStatus::TimedOut => format!(
+                "Not mined after {} minutes, check it in the explorer", options.timeout.as_secs() / 60
+            ),
+        }
     }
-}
\ No newline at end of file
+}
+
+fn gas_message(receipt: &TransactionReceipt) -> String {
+    let gas_used = receipt.gas_used.unwrap_or_default();
+    match receipt.effective_gas_price {
+        Some(price) => format!(
+            "Gas used: {}, effective gas price: {} gwei",
+            gas_used,
+            format_units(price, "gwei").unwrap_or_default()
+        ),
+        None => format!("Gas used: {}", gas_used),
+    }
+}
@@ -0,0 +1,228 @@
+use yew::{prelude::*, context::ContextHandle};
+use ethers::core::types::{Address, Signature};
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use crate::{
+    components::{address_input::AddressInput, signature_check::{signer_check, view_verified, Verified}},
+    ethereum::{describe_typed_data, parse_chain_id, parse_signature, parse_typed_data, recover_typed_data_signer, typed_data_hash, DappError, TypedData},
+    wallet::{sign_typed_data, WalletContext},
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct TypedDataSignerProps {
+    #[prop_or_default]
+    pub on_signed: Callback<(TypedData, Signature)>,
+}
+
+pub struct TypedDataSigner {
+    wallet: WalletContext,
+    _wallet_handle: ContextHandle<WalletContext>,
+    // checked payload, as typed and parsed
+    typed_data: Option<(String, TypedData)>,
+    signing: bool,
+    signed: Option<(Signature, Result<Address, DappError>)>, // and the recovered signer
+    error: Option<DappError>,
+    verified: Option<Verified>,
+    verify_address: Option<Address>, // typed or resolved
+    // handle refs for DOM elements
+    payload: NodeRef,
+    verify_signature: NodeRef,
+}
+
+pub enum TypedDataSignerMsg {
+    WalletChanged(WalletContext),
+    Check,
+    Sign,
+    Signed(Signature),
+    SetError(DappError),
+    SetVerifyAddress(Option<Address>),
+    Verify,
+}
+
+impl Component for TypedDataSigner {
+    type Message = TypedDataSignerMsg;
+    type Properties = TypedDataSignerProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (wallet, wallet_handle) = ctx.link()
+            .context::<WalletContext>(ctx.link().callback(TypedDataSignerMsg::WalletChanged))
+            .expect("TypedDataSigner needs a WalletContext provider");
+        Self {
+            wallet,
+            _wallet_handle: wallet_handle,
+            typed_data: None,
+            signing: false,
+            signed: None,
+            error: None,
+            verified: None,
+            verify_address: None,
+            payload: NodeRef::default(),
+            verify_signature: NodeRef::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TypedDataSignerMsg::WalletChanged(wallet) => {
+                let changed = wallet.address != self.wallet.address
+                    || wallet.chain_id != self.wallet.chain_id;
+                self.wallet = wallet;
+                if changed {
+                    self.signed = None;
+                }
+                changed
+            }
+            TypedDataSignerMsg::Check => {
+                let payload = self.payload.cast::<HtmlTextAreaElement>().map(|input| input.value()).unwrap_or_default();
+                self.signed = None;
+                self.verified = None;
+                match parse_typed_data(&payload) {
+                    Ok(typed_data) => {
+                        self.typed_data = Some((payload, typed_data));
+                        self.error = None;
+                    }
+                    Err(err) => {
+                        self.typed_data = None;
+                        self.error = Some(err);
+                    }
+                }
+                true
+            }
+            TypedDataSignerMsg::Sign => {
+                let address = match self.wallet.address.as_deref().map(DappError::parse_address) {
+                    Some(Ok(address)) => address,
+                    _ => return false
+                };
+                let payload = match &self.typed_data {
+                    Some((payload, _)) => payload.clone(),
+                    None => return false
+                };
+                self.error = None;
+                self.signed = None;
+                self.signing = true;
+                ctx.link().send_future(async move {
+                    match sign_typed_data(&payload, address).await {
+                        Ok(signature) => TypedDataSignerMsg::Signed(signature),
+                        Err(err) => TypedDataSignerMsg::SetError(err),
+                    }
+                });
+                true
+            }
+            TypedDataSignerMsg::Signed(signature) => {
+                self.signing = false;
+                if let Some((_, typed_data)) = &self.typed_data {
+                    self.signed = Some((signature, recover_typed_data_signer(typed_data, &signature)));
+                    ctx.props().on_signed.emit((typed_data.clone(), signature));
+                }
+                true
+            }
+            TypedDataSignerMsg::SetError(err) => {
+                self.signing = false;
+                self.error = Some(err);
+                true
+            }
+            TypedDataSignerMsg::SetVerifyAddress(address) => {
+                self.verify_address = address;
+                false
+            }
+            TypedDataSignerMsg::Verify => {
+                let typed_data = match &self.typed_data {
+                    Some((_, typed_data)) => typed_data,
+                    None => return false
+                };
+                let signature = self.verify_signature.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+                self.verified = Some(parse_signature(&signature).and_then(|signature| {
+                    let expected = self.verify_address.ok_or_else(|| DappError::MissingAddress("signer".to_string()))?;
+                    Ok((recover_typed_data_signer(typed_data, &signature)?, expected))
+                }));
+                true
+            }
+        }
+    }
+
+    // html! checks the AddressInput props with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        html! {
+            <div>
+                <h3>{"Sign typed data (EIP-712)"}</h3>
+                <textarea
+                    ref={&self.payload}
+                    rows="12"
+                    cols="60"
+                    placeholder="eth_signTypedData_v4 JSON: types, primaryType, domain and message"
+                    onchange={link.callback(|_| TypedDataSignerMsg::Check)}
+                />
+                <div>
+                    <button onclick={link.callback(|_| TypedDataSignerMsg::Check)}>{"Check"}</button>
+                    if self.wallet.address.is_some() && self.typed_data.is_some() {
+                        <button disabled={self.signing} onclick={link.callback(|_| TypedDataSignerMsg::Sign)}>
+                            { if self.signing { "Waiting for the wallet.." } else { "Sign" } }
+                        </button>
+                    }
+                </div>
+                if let Some(err) = &self.error {
+                    <p>{err.to_string()}</p>
+                }
+                if let Some((_, typed_data)) = &self.typed_data {
+                    { self.view_typed_data(typed_data) }
+                }
+                if let Some((signature, signer)) = &self.signed {
+                    <p>
+                        {"Signature: "}<code>{format!("0x{}", signature)}</code>
+                        <br />
+                        { signer_check(&self.wallet, signer) }
+                    </p>
+                }
+                if self.typed_data.is_some() {
+                    <h4>{"Verify a signature of this data"}</h4>
+                    <div>
+                        <input type="text" ref={&self.verify_signature} placeholder="Signature" />
+                        <AddressInput placeholder="Signer address" on_change={link.callback(TypedDataSignerMsg::SetVerifyAddress)} />
+                        <button onclick={link.callback(|_| TypedDataSignerMsg::Verify)}>
+                            {"Verify"}
+                        </button>
+                    </div>
+                }
+                { view_verified(&self.verified) }
+            </div>
+        }
+    }
+}
+
+impl TypedDataSigner {
+    fn view_typed_data(&self, typed_data: &TypedData) -> Html {
+        // wallets refuse to sign for another chain than the one they are on
+        let wrong_chain = match (typed_data.domain.chain_id, self.wallet.chain_id.as_deref()) {
+            (Some(domain_chain), Some(wallet_chain)) => {
+                parse_chain_id(wallet_chain).map(Into::into) != Some(domain_chain)
+            }
+            _ => false,
+        };
+        html! {
+            <div>
+                <table>
+                    { for describe_typed_data(typed_data).into_iter().map(|field| html! {
+                        <tr>
+                            <td style={format!("padding-left: {}em", field.depth * 2)}>
+                                if field.value.is_none() {
+                                    <b>{&field.name}</b>
+                                } else {
+                                    {&field.name}
+                                }
+                            </td>
+                            <td><small>{&field.r#type}</small></td>
+                            <td>{field.value.unwrap_or_default()}</td>
+                        </tr>
+                    })}
+                </table>
+                if let Ok(hash) = typed_data_hash(typed_data) {
+                    <p><small>{format!("Hash: {:?}", hash)}</small></p>
+                }
+                if wrong_chain {
+                    <p>{"The domain chainId is not the chain of the wallet, it will refuse to sign"}</p>
+                }
+            </div>
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use yew::prelude::*;
+use wasm_bindgen::prelude::*;
+use crate::wallet::{WalletInfo, INJECTED_UUID, discoverWallets, stopWalletDiscovery, hasInjectedWallet};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct WalletPickerProps {
+    pub on_select: Callback<WalletInfo>,
+}
+
+pub struct WalletPicker {
+    wallets: Vec<WalletInfo>,
+    // keeps the announce listener alive until the picker is removed
+    _on_announce: Closure<dyn Fn(JsValue)>,
+    listener: JsValue,
+}
+
+pub enum WalletPickerMsg {
+    Announced(WalletInfo),
+}
+
+impl Component for WalletPicker {
+    type Message = WalletPickerMsg;
+    type Properties = WalletPickerProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let announced = ctx.link().batch_callback(|info: JsValue| {
+            WalletInfo::from_announcement(info).map(WalletPickerMsg::Announced)
+        });
+        let on_announce = Closure::wrap(Box::new(move |info| announced.emit(info)) as Box<dyn Fn(JsValue)>);
+        let listener = discoverWallets(&on_announce);
+        let mut wallets = Vec::new();
+        if hasInjectedWallet() {
+            wallets.push(WalletInfo::injected());
+        }
+        Self {
+            wallets,
+            _on_announce: on_announce,
+            listener,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            WalletPickerMsg::Announced(info) => {
+                if self.wallets.iter().any(|wallet| wallet.uuid == info.uuid) {
+                    return false
+                }
+                // window.ethereum is usually one of the announced wallets
+                self.wallets.retain(|wallet| wallet.uuid != INJECTED_UUID);
+                self.wallets.push(info);
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div>
+                if self.wallets.is_empty() {
+                    <p>{"No wallet found, install a browser wallet to continue"}</p>
+                }
+                { for self.wallets.iter().map(|wallet| {
+                    let info = wallet.clone();
+                    let on_select = ctx.props().on_select.clone();
+                    html! {
+                        <button onclick={Callback::from(move |_| on_select.emit(info.clone()))}>
+                            if !wallet.icon.is_empty() {
+                                <img src={wallet.icon.clone()} alt={wallet.name.clone()} width="20" height="20" />
+                            }
+                            {" "}{&wallet.name}
+                        </button>
+                    }
+                })}
+            </div>
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        stopWalletDiscovery(&self.listener);
+    }
+}
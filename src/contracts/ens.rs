@@ -0,0 +1,158 @@
+use ethers::{contract::abigen, prelude::*, providers::ens::{namehash, reverse_address, ENS_ADDRESS}};
+use std::sync::Arc;
+use crate::ethereum::{DappError, ProviderHandle};
+
+// only the views used to resolve names are bound
+abigen!(
+    EnsRegistry,
+    "src/abis/ens_registry.json"
+);
+abigen!(
+    EnsResolver,
+    "src/abis/ens_resolver.json"
+);
+
+// Keeps the ENS answers, the app keeps them for the session in sessionStorage
+// (storage::BrowserCache).
+pub trait EnsStore {
+    fn address(&self, chain_id: u64, name: &str) -> Option<Address>;
+    fn insert_address(&self, chain_id: u64, name: &str, address: Address);
+    // Some(None) when the address is known to have no name
+    fn name(&self, chain_id: u64, address: &Address) -> Option<Option<String>>;
+    fn insert_name(&self, chain_id: u64, address: &Address, name: Option<String>);
+}
+
+// Labels separated by dots (vitalik.eth, sub.name.eth, 0xfoo.eth, DNS names
+// imported in ENS..). Inputs are parsed as an address first, a name can start
+// with 0x.
+pub fn is_ens_name(input: &str) -> bool {
+    let input = input.trim();
+    input.contains('.')
+        && input.split('.').all(|label| !label.is_empty() && !label.contains(char::is_whitespace))
+}
+
+// lowercased, the registry only knows normalized names (full UTS-46 is not applied)
+pub fn normalize_ens_name(input: &str) -> Result<String, DappError> {
+    if !is_ens_name(input) {
+        return Err(DappError::InvalidEnsName(input.to_string()))
+    }
+    Ok(input.trim().to_lowercase())
+}
+
+// Address the name points to on the network of provider, from the store once
+// resolved.
+pub async fn resolve_ens_name(provider: &ProviderHandle, store: &impl EnsStore, name: &str) -> Result<Address, DappError> {
+    let name = normalize_ens_name(name)?;
+    let chain_id = provider.network().chain_id;
+    if let Some(address) = store.address(chain_id, &name) {
+        return Ok(address)
+    }
+    let client = provider.get().await?;
+    let address = query_address(client, &name).await?
+        .ok_or_else(|| DappError::EnsNotFound(name.clone()))?;
+    store.insert_address(chain_id, &name, address);
+    Ok(address)
+}
+
+// Primary name of address, only when the name resolves back to it: anyone can
+// set any name as the reverse record of their address.
+// None as well on networks without ENS. Each address is looked up once per
+// network, the handle shares the answer with every caller and it is kept in the
+// store.
+pub async fn lookup_ens_name(
+    provider: &ProviderHandle,
+    store: impl EnsStore + 'static,
+    address: Address,
+) -> Result<Option<String>, DappError> {
+    let lookup_provider = provider.clone();
+    provider.lookup_name(address, move || verified_name(lookup_provider, store, address)).await
+}
+
+async fn verified_name(provider: ProviderHandle, store: impl EnsStore, address: Address) -> Result<Option<String>, DappError> {
+    let chain_id = provider.network().chain_id;
+    if let Some(name) = store.name(chain_id, &address) {
+        return Ok(name)
+    }
+    let client = provider.get().await?;
+    let verified = match query_name(Arc::clone(&client), address).await {
+        Ok(Some(name)) => match query_address(client, &name).await? {
+            Some(forward) if forward == address => Some(name),
+            _ => None,
+        },
+        Ok(None) | Err(DappError::EnsUnavailable) => None,
+        Err(err) => return Err(err),
+    };
+    if let Some(name) = &verified {
+        store.insert_address(chain_id, name, address);
+    }
+    store.insert_name(chain_id, &address, verified.clone());
+    Ok(verified)
+}
+
+// resolver of the name in the registry, None when the name has none
+async fn query_resolver<P: JsonRpcClient + 'static>(client: Arc<Provider<P>>, node: H256) -> Result<Option<Address>, DappError> {
+    let registry = EnsRegistry::new(ENS_ADDRESS, client);
+    match registry.resolver(node.0).call().await {
+        Ok(resolver) if resolver.is_zero() => Ok(None),
+        Ok(resolver) => Ok(Some(resolver)),
+        // no registry at the address, the empty answer does not decode
+        Err(ContractError::DetokenizationError(_)) | Err(ContractError::AbiError(_)) => Err(DappError::EnsUnavailable),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn query_address<P: JsonRpcClient + 'static>(client: Arc<Provider<P>>, name: &str) -> Result<Option<Address>, DappError> {
+    let node = namehash(name);
+    let resolver = match query_resolver(Arc::clone(&client), node).await? {
+        Some(resolver) => resolver,
+        None => return Ok(None)
+    };
+    match EnsResolver::new(resolver, client).addr(node.0).call().await.map_err(DappError::from) {
+        Ok(address) if address.is_zero() => Ok(None),
+        Ok(address) => Ok(Some(address)),
+        // resolvers without an address record revert or answer nothing
+        Err(DappError::ContractRevert(_)) | Err(DappError::Decoding(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+async fn query_name<P: JsonRpcClient + 'static>(client: Arc<Provider<P>>, address: Address) -> Result<Option<String>, DappError> {
+    let node = namehash(&reverse_address(address));
+    let resolver = match query_resolver(Arc::clone(&client), node).await? {
+        Some(resolver) => resolver,
+        None => return Ok(None)
+    };
+    match EnsResolver::new(resolver, client).name(node.0).call().await.map_err(DappError::from) {
+        Ok(name) if name.is_empty() => Ok(None),
+        Ok(name) => Ok(Some(name)),
+        Err(DappError::ContractRevert(_)) | Err(DappError::Decoding(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn recognizes_names() {
+        for name in ["vitalik.eth", "sub.name.eth", " Nick.ETH ", "example.xyz", "0xfoo.eth", "0x.eth"] {
+            assert!(is_ens_name(name), "{}", name);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_addresses_and_malformed_names() {
+        let inputs = ["0x6B175474E89094C44Da98b954EedeAC495271d0F", "eth", "vitalik..eth", ".eth", "vitalik.", "my name.eth", ""];
+        for input in inputs {
+            assert!(!is_ens_name(input), "{}", input);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn normalizes_names() {
+        assert_eq!(normalize_ens_name(" Nick.ETH "), Ok("nick.eth".to_string()));
+        assert_eq!(normalize_ens_name("eth"), Err(DappError::InvalidEnsName("eth".to_string())));
+    }
+}
@@ -0,0 +1,293 @@
+use ethers::{abi::{Detokenize, RawLog}, contract::{abigen, decode_function_data}, prelude::*};
+use std::{collections::HashMap, sync::Arc};
+use crate::{
+    contracts::{metadata::{decode_metadata, decode_supply, metadata_calls, TokenMetadata, METADATA_CALLS}, multicall::aggregate},
+    ethereum::{get_logs_chunked, DappError},
+};
+
+abigen!(
+    IERC20,
+    "src/abis/erc20.json",
+    event_derives(serde::Deserialize, serde::Serialize)
+);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ERC20Information {
+    pub metadata: TokenMetadata,
+    pub total_supply: Option<U256>, // None when totalSupply() reverted
+    pub balance: U256,
+    pub block: u64, // the balance was read at
+}
+
+// Checks there is a contract at token_address answering the ERC20 views before
+// it gets tracked, returns its metadata.
+pub async fn check_erc20<P: JsonRpcClient + 'static>(client: Arc<Provider<P>>, token_address: Address) -> Result<TokenMetadata, DappError> {
+    let code = client.get_code(token_address, None).await?;
+    if code.as_ref().is_empty() {
+        return Err(DappError::NotAContract(format!("{:?}", token_address)))
+    }
+    let token_contract = IERC20::new(token_address, Arc::clone(&client));
+    let mut calls: Vec<(Address, Bytes)> = metadata_calls(&token_contract).into_iter()
+        .map(|data| (token_address, data))
+        .collect();
+    calls.push((token_address, token_contract.total_supply().calldata().unwrap_or_default()));
+    calls.push((token_address, token_contract.balance_of(Address::zero()).calldata().unwrap_or_default()));
+    calls.push((token_address, token_contract.allowance(Address::zero(), Address::zero()).calldata().unwrap_or_default()));
+    let answers = aggregate(client, calls, None).await?;
+    let (metadata, views) = answers.split_at(METADATA_CALLS);
+    decode_answer::<U256>("totalSupply", &views[0])?;
+    let metadata = decode_metadata(token_address, metadata, true)?;
+    decode_answer::<U256>("balanceOf", &views[1])?;
+    decode_answer::<U256>("allowance", &views[2])?;
+    Ok(metadata)
+}
+
+
+pub async fn fetch_erc20_information<P: JsonRpcClient + 'static>(client: Arc<Provider<P>>, token_address: &str, user_address: String) -> Result<ERC20Information, DappError>  {
+    let address = DappError::parse_address(token_address)?;
+    let user_address_parsed = DappError::parse_address(&user_address)?;
+    fetch_erc20_batch(client, &[(address, user_address_parsed)], &HashMap::new()).await?
+        .pop()
+        .expect("one answer per token")
+}
+
+// User balance of every (token, user) pair, read in a single Multicall3 call at
+// the same block, with the metadata of the tokens missing from known.
+// Tokens that don't answer get their own error.
+pub async fn fetch_erc20_batch<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    requests: &[(Address, Address)],
+    known: &HashMap<Address, TokenMetadata>,
+) -> Result<Vec<Result<ERC20Information, DappError>>, DappError> {
+    let block = client.get_block_number().await?;
+    let mut calls = Vec::with_capacity(requests.len() * (2 + METADATA_CALLS));
+    for (token, user) in requests {
+        let token_contract = IERC20::new(*token, Arc::clone(&client));
+        calls.push((*token, token_contract.balance_of(*user).calldata().unwrap_or_default()));
+        calls.push((*token, token_contract.total_supply().calldata().unwrap_or_default()));
+        if !known.contains_key(token) {
+            calls.extend(metadata_calls(&token_contract).into_iter().map(|data| (*token, data)));
+        }
+    }
+    let mut answers = aggregate(client, calls, Some(BlockId::Number(block.into()))).await?.into_iter();
+    Ok(requests.iter()
+        .map(|(token, _)| {
+            // all taken before decoding so the next token starts at its own answers
+            let balance = answers.next().flatten();
+            let total_supply = decode_supply(&answers.next().flatten());
+            let metadata = match known.get(token) {
+                Some(metadata) => Ok(metadata.clone()),
+                None => decode_metadata(*token, &answers.by_ref().take(METADATA_CALLS).collect::<Vec<_>>(), total_supply.is_some()),
+            };
+            Ok(ERC20Information {
+                metadata: metadata?,
+                total_supply,
+                balance: decode_answer("balanceOf", &balance)?,
+                block: block.as_u64(),
+            })
+        })
+        .collect())
+}
+
+// None is a reverted call
+fn decode_answer<D: Detokenize>(function: &str, answer: &Option<Bytes>) -> Result<D, DappError> {
+    let data = answer.as_ref()
+        .ok_or_else(|| DappError::Decoding(format!("{} reverted", function)))?;
+    let function = IERC20_ABI.function(function)
+        .map_err(|err| DappError::Decoding(err.to_string()))?;
+    decode_function_data(function, data, false)
+        .map_err(|err| DappError::Decoding(err.to_string()))
+}
+
+// client is expected to be a wallet backed provider (Provider<Eip1193>), which signs and sends
+pub async fn transfer_erc20<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    token_address: Address,
+    from: Address,
+    recipient: Address,
+    amount: U256,
+) -> Result<H256, DappError> {
+    let token_contract = IERC20::new(token_address, client);
+    let call = token_contract.transfer(recipient, amount).from(from);
+    let pending = call.send().await?;
+    Ok(pending.tx_hash())
+}
+
+// allowance granted by owner to each spender, in the same order
+pub async fn fetch_allowances<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    token_address: Address,
+    owner: Address,
+    spenders: &[Address],
+) -> Result<Vec<U256>, DappError> {
+    let token_contract = IERC20::new(token_address, client);
+    let mut allowances = Vec::with_capacity(spenders.len());
+    for spender in spenders {
+        allowances.push(token_contract.allowance(owner, *spender).call().await?);
+    }
+    Ok(allowances)
+}
+
+// U256::MAX for an unlimited approval, zero to revoke
+pub async fn approve_erc20<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    token_address: Address,
+    from: Address,
+    spender: Address,
+    amount: U256,
+) -> Result<H256, DappError> {
+    let token_contract = IERC20::new(token_address, client);
+    let call = token_contract.approve(spender, amount).from(from);
+    let pending = call.send().await?;
+    Ok(pending.tx_hash())
+}
+
+// spends the allowance owner gave to the sender (from)
+pub async fn transfer_from_erc20<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    token_address: Address,
+    from: Address,
+    owner: Address,
+    recipient: Address,
+    amount: U256,
+) -> Result<H256, DappError> {
+    let token_contract = IERC20::new(token_address, client);
+    let call = token_contract.transfer_from(owner, recipient, amount).from(from);
+    let pending = call.send().await?;
+    Ok(pending.tx_hash())
+}
+
+// allowances above this are shown as unlimited, spending from a U256::MAX
+// approval lowers it on some tokens
+pub fn is_unlimited(allowance: U256) -> bool {
+    allowance >= U256::MAX >> 1
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenApproval {
+    pub token: Address,
+    pub spender: Address,
+    pub symbol: Option<String>, // None when the token does not answer
+    pub decimals: Option<u8>,
+    pub allowance: U256, // live value, not the one of the event
+}
+
+// Approval events emitted for owner in the block range, reduced to the
+// token/spender pairs that can still spend something.
+pub async fn scan_approvals<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    owner: Address,
+    from_block: u64,
+    to_block: u64,
+    on_progress: impl Fn(u64),
+) -> Result<Vec<TokenApproval>, DappError> {
+    let filter = Filter::new()
+        .topic0(ApprovalFilter::signature())
+        .topic1(owner);
+    let logs = get_logs_chunked(&client, &filter, from_block, to_block, on_progress).await?;
+    let mut pairs: Vec<(Address, Address)> = Vec::new();
+    for log in logs {
+        let token = log.address;
+        // ERC721 approvals share the signature but index the token id, they don't decode
+        let event = match <ApprovalFilter as EthEvent>::decode_log(&RawLog { topics: log.topics, data: log.data.to_vec() }) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        if !pairs.contains(&(token, event.guy)) {
+            pairs.push((token, event.guy));
+        }
+    }
+    let mut approvals = Vec::new();
+    let mut metadata: HashMap<Address, (Option<String>, Option<u8>)> = HashMap::new();
+    for (token, spender) in pairs {
+        let token_contract = IERC20::new(token, Arc::clone(&client));
+        let allowance = match token_contract.allowance(owner, spender).call().await {
+            Ok(allowance) => allowance,
+            Err(err) => {
+                log::warn!("allowance of {:?} failed: {}", token, err);
+                continue
+            }
+        };
+        if allowance.is_zero() {
+            continue
+        }
+        let (symbol, decimals) = match metadata.get(&token) {
+            Some(known) => known.clone(),
+            None => {
+                let symbol = token_contract.symbol().call().await.ok();
+                let decimals = token_contract.decimals().call().await.ok();
+                metadata.insert(token, (symbol.clone(), decimals));
+                (symbol, decimals)
+            }
+        };
+        approvals.push(TokenApproval { token, spender, symbol, decimals, allowance });
+    }
+    Ok(approvals)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferDirection {
+    In,
+    Out,
+    ToSelf,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenTransfer {
+    pub tx_hash: H256,
+    pub log_index: U256,
+    pub block: u64,
+    pub timestamp: Option<u64>, // unix seconds, None if the block could not be fetched
+    pub direction: TransferDirection,
+    pub counterparty: Address,
+    pub amount: U256,
+}
+
+// Transfer events of token sent or received by account in the block range, newest first.
+pub async fn fetch_transfers<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    token_address: Address,
+    account: Address,
+    from_block: u64,
+    to_block: u64,
+    on_progress: impl Fn(u64),
+) -> Result<Vec<TokenTransfer>, DappError> {
+    let filter = Filter::new()
+        .address(token_address)
+        .topic0(TransferFilter::signature());
+    let sent = get_logs_chunked(&client, &filter.clone().topic1(account), from_block, to_block, &on_progress).await?;
+    let received = get_logs_chunked(&client, &filter.topic2(account), from_block, to_block, &on_progress).await?;
+    let mut transfers: Vec<TokenTransfer> = Vec::new();
+    let mut timestamps: HashMap<u64, Option<u64>> = HashMap::new();
+    for log in sent.into_iter().chain(received) {
+        let (tx_hash, log_index, block) = match (log.transaction_hash, log.log_index, log.block_number) {
+            (Some(tx_hash), Some(log_index), Some(block)) => (tx_hash, log_index, block.as_u64()),
+            _ => continue, // pending logs
+        };
+        // a transfer to self matches both queries
+        if transfers.iter().any(|known| known.tx_hash == tx_hash && known.log_index == log_index) {
+            continue
+        }
+        let event = match <TransferFilter as EthEvent>::decode_log(&RawLog { topics: log.topics, data: log.data.to_vec() }) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        let (direction, counterparty) = match (event.src == account, event.dst == account) {
+            (true, true) => (TransferDirection::ToSelf, account),
+            (true, false) => (TransferDirection::Out, event.dst),
+            _ => (TransferDirection::In, event.src),
+        };
+        let timestamp = match timestamps.get(&block) {
+            Some(timestamp) => *timestamp,
+            None => {
+                let timestamp = client.get_block(block).await.ok().flatten()
+                    .map(|block| block.timestamp.as_u64());
+                timestamps.insert(block, timestamp);
+                timestamp
+            }
+        };
+        transfers.push(TokenTransfer { tx_hash, log_index, block, timestamp, direction, counterparty, amount: event.wad });
+    }
+    transfers.sort_by_key(|transfer| std::cmp::Reverse((transfer.block, transfer.log_index)));
+    Ok(transfers)
+}
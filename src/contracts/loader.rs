@@ -0,0 +1,94 @@
+use ethers::prelude::*;
+use futures::channel::oneshot;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use crate::{
+    contracts::{erc20::{fetch_erc20_batch, ERC20Information}, metadata::TokenMetadata},
+    ethereum::{DappError, ProviderHandle},
+};
+
+// Keeps token metadata between page loads. The frontend provides it, the app
+// stores it in localStorage (storage::BrowserCache).
+pub trait MetadataStore {
+    // metadata of the tokens already known on the network
+    fn metadata(&self, chain_id: u64, tokens: &[Address]) -> HashMap<Address, TokenMetadata>;
+    fn insert_metadata(&self, chain_id: u64, metadata: Vec<(Address, TokenMetadata)>);
+}
+
+struct Request {
+    provider: ProviderHandle,
+    store: Rc<dyn MetadataStore>,
+    token: Address,
+    user: Address,
+    reply: oneshot::Sender<Result<ERC20Information, DappError>>,
+}
+
+thread_local! {
+    // requests waiting for the next flush
+    static QUEUE: RefCell<Vec<Request>> = const { RefCell::new(Vec::new()) };
+}
+
+// Same as fetch_erc20_information, but requests made while the page renders are
+// queued and sent together, so every TokenCard on screen loads in one Multicall3 call.
+// Token metadata comes from the store once it has been read.
+pub async fn load_erc20_information(
+    provider: ProviderHandle,
+    store: impl MetadataStore + 'static,
+    token: Address,
+    user: Address,
+) -> Result<ERC20Information, DappError> {
+    let (reply, answer) = oneshot::channel();
+    let first = QUEUE.with(|queue| {
+        let mut queue = queue.borrow_mut();
+        queue.push(Request { provider, store: Rc::new(store), token, user, reply });
+        queue.len() == 1
+    });
+    // runs once the futures already spawned had a chance to queue theirs
+    if first {
+        wasm_bindgen_futures::spawn_local(flush());
+    }
+    answer.await
+        .unwrap_or_else(|_| Err(DappError::Transport("the request was dropped".to_string())))
+}
+
+async fn flush() {
+    let mut requests = QUEUE.with(|queue| std::mem::take(&mut *queue.borrow_mut()));
+    // one batch per network
+    while let Some(first) = requests.first() {
+        let provider = first.provider.clone();
+        let store = Rc::clone(&first.store);
+        let (batch, rest): (Vec<Request>, Vec<Request>) = requests.into_iter()
+            .partition(|request| request.provider == provider);
+        requests = rest;
+        let pairs: Vec<(Address, Address)> = batch.iter()
+            .map(|request| (request.token, request.user))
+            .collect();
+        let chain_id = provider.network().chain_id;
+        let tokens: Vec<Address> = batch.iter().map(|request| request.token).collect();
+        let known = store.metadata(chain_id, &tokens);
+        let answers = match provider.get().await {
+            Ok(client) => fetch_erc20_batch(client, &pairs, &known).await,
+            Err(err) => Err(err),
+        };
+        match answers {
+            Ok(answers) => {
+                let mut resolved = Vec::new();
+                for (request, answer) in batch.into_iter().zip(answers) {
+                    if let Ok(information) = &answer {
+                        if !known.contains_key(&request.token) {
+                            resolved.push((request.token, information.metadata.clone()));
+                        }
+                    }
+                    let _ = request.reply.send(answer);
+                }
+                if !resolved.is_empty() {
+                    store.insert_metadata(chain_id, resolved);
+                }
+            }
+            Err(err) => {
+                for request in batch {
+                    let _ = request.reply.send(Err(err.clone()));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use ethers::{abi::{self, ParamType, Token}, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use crate::{
+    contracts::{erc20::IERC20, multicall::aggregate},
+    ethereum::DappError,
+    helpers::short_address,
+};
+
+// calls made per token, decoded by decode_metadata in this order
+pub(crate) const METADATA_CALLS: usize = 3;
+// U256 has 78 digits, format_units fails above this
+const MAX_DECIMALS: u8 = 77;
+
+// What a token says about itself and can't change, read once and cached (see
+// storage). The supply moves and is read with the balance.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub symbol: String,
+    pub name: String,
+    pub decimals: Option<u8>, // None when the token has no decimals() or a value above MAX_DECIMALS
+}
+
+impl TokenMetadata {
+    // decimals() is optional in ERC20, amounts of tokens without it are shown in base units
+    pub fn display_decimals(&self) -> i32 {
+        // caches written before MAX_DECIMALS was checked
+        self.decimals.filter(|decimals| *decimals <= MAX_DECIMALS).unwrap_or_default().into()
+    }
+}
+
+pub(crate) fn metadata_calls<M: Middleware>(token: &IERC20<M>) -> [Bytes; METADATA_CALLS] {
+    [
+        token.symbol().calldata().unwrap_or_default(),
+        token.name().calldata().unwrap_or_default(),
+        token.decimals().calldata().unwrap_or_default(),
+    ]
+}
+
+// Metadata of every token in a single Multicall3 call.
+pub async fn fetch_token_metadata<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    tokens: &[Address],
+) -> Result<Vec<Result<TokenMetadata, DappError>>, DappError> {
+    let mut calls = Vec::with_capacity(tokens.len() * (METADATA_CALLS + 1));
+    for token in tokens {
+        let token_contract = IERC20::new(*token, Arc::clone(&client));
+        calls.extend(metadata_calls(&token_contract).into_iter().map(|data| (*token, data)));
+        calls.push((*token, token_contract.total_supply().calldata().unwrap_or_default()));
+    }
+    let answers = aggregate(client, calls, None).await?;
+    Ok(tokens.iter()
+        .zip(answers.chunks(METADATA_CALLS + 1))
+        .map(|(token, answers)| {
+            let (metadata, supply) = answers.split_at(METADATA_CALLS);
+            decode_metadata(*token, metadata, decode_supply(&supply[0]).is_some())
+        })
+        .collect())
+}
+
+// totalSupply() answer, None when it reverted
+pub(crate) fn decode_supply(answer: &Option<Bytes>) -> Option<U256> {
+    answer.as_ref().and_then(|data| decode_uint(data))
+}
+
+// Every field but the symbol is optional, a missing symbol is replaced by the
+// address. Only fails when neither symbol() nor totalSupply() (has_supply) answer.
+pub(crate) fn decode_metadata(token: Address, answers: &[Option<Bytes>], has_supply: bool) -> Result<TokenMetadata, DappError> {
+    let answer = |index: usize| answers.get(index).and_then(Option::as_ref).map(|data| data.as_ref());
+    let symbol = answer(0).and_then(decode_text);
+    let name = answer(1).and_then(decode_text);
+    let decimals = answer(2)
+        .and_then(decode_uint)
+        .and_then(|decimals| u8::try_from(decimals).ok())
+        .filter(|decimals| *decimals <= MAX_DECIMALS);
+    if symbol.is_none() && !has_supply {
+        return Err(DappError::Decoding(format!("{:?} does not answer as an ERC20 token", token)))
+    }
+    let symbol = symbol.unwrap_or_else(|| short_address(&format!("{:?}", token)));
+    Ok(TokenMetadata {
+        name: name.unwrap_or_else(|| symbol.clone()),
+        symbol,
+        decimals,
+    })
+}
+
+// string as in the standard, or bytes32 padded with zeros (MKR, SAI..)
+fn decode_text(data: &[u8]) -> Option<String> {
+    if let Ok(mut tokens) = abi::decode(&[ParamType::String], data) {
+        if let Some(Token::String(text)) = tokens.pop() {
+            return Some(text.trim().to_string()).filter(|text| !text.is_empty())
+        }
+    }
+    if data.len() == 32 {
+        let end = data.iter().position(|byte| *byte == 0).unwrap_or(data.len());
+        let text = String::from_utf8_lossy(&data[..end]).trim().to_string();
+        return Some(text).filter(|text| !text.is_empty())
+    }
+    None
+}
+
+pub(crate) fn decode_uint(data: &[u8]) -> Option<U256> {
+    match abi::decode(&[ParamType::Uint(256)], data).ok()?.pop()? {
+        Token::Uint(value) => Some(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn bytes32(text: &str) -> Vec<u8> {
+        let mut data = text.as_bytes().to_vec();
+        data.resize(32, 0);
+        data
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_string() {
+        let data = abi::encode(&[Token::String(" Dai Stablecoin ".to_string())]);
+        assert_eq!(decode_text(&data), Some("Dai Stablecoin".to_string()));
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_bytes32() {
+        assert_eq!(decode_text(&bytes32("MKR")), Some("MKR".to_string()));
+        assert_eq!(decode_text(&bytes32("Maker")), Some("Maker".to_string()));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_empty_or_malformed_text() {
+        assert_eq!(decode_text(&bytes32("")), None);
+        assert_eq!(decode_text(&abi::encode(&[Token::String(String::new())])), None);
+        assert_eq!(decode_text(&[0x4d, 0x4b, 0x52]), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn treats_too_many_decimals_as_unknown() {
+        let token = Address::repeat_byte(0x11);
+        let uint = |value: u64| Some(Bytes::from(abi::encode(&[Token::Uint(value.into())])));
+        let answers = |decimals| [Some(bytes32("MKR").into()), None, uint(decimals)];
+        assert_eq!(decode_metadata(token, &answers(77), false).unwrap().decimals, Some(77));
+        assert_eq!(decode_metadata(token, &answers(78), false).unwrap().decimals, None);
+        assert_eq!(decode_metadata(token, &answers(256), false).unwrap().decimals, None);
+    }
+}
@@ -0,0 +1,25 @@
+// Typed bindings for the contracts the dapp talks to, generic over the provider
+// so they work both on the read-only websocket and through the wallet.
+mod ens;
+mod erc20;
+mod loader;
+mod metadata;
+mod multicall;
+mod permit;
+mod token_list;
+
+pub use erc20::{
+    IERC20, IERC20_ABI, ERC20Information, TokenApproval, is_unlimited,
+    TokenTransfer, TransferDirection, fetch_transfers,
+    check_erc20, fetch_erc20_information, fetch_erc20_batch, fetch_allowances, scan_approvals,
+    transfer_erc20, approve_erc20, transfer_from_erc20,
+};
+pub use ens::{EnsStore, is_ens_name, lookup_ens_name, normalize_ens_name, resolve_ens_name};
+pub use loader::{load_erc20_information, MetadataStore};
+pub use metadata::{fetch_token_metadata, TokenMetadata};
+pub use multicall::{aggregate, Multicall3, MULTICALL3_ADDRESS};
+pub use permit::{
+    IERC20Permit, IERC20PERMIT_ABI, Permit, PermitSignature, PermitSupport,
+    fetch_permit_support, permit_typed_data, submit_permit,
+};
+pub use token_list::{TokenInfo, TokenList, TokenListVersion, bundled_token_list, parse_token_list};
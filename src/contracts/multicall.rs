@@ -0,0 +1,66 @@
+use ethers::{contract::abigen, prelude::*, types::transaction::eip2718::TypedTransaction};
+use std::sync::Arc;
+use crate::ethereum::DappError;
+
+// same address on every chain it is deployed to, see https://www.multicall3.com
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+// only aggregate3 is bound
+abigen!(
+    Multicall3,
+    "src/abis/multicall3.json"
+);
+
+// Runs every (target, calldata) call in a single eth_call through Multicall3's
+// aggregate3, a call that reverts gives None instead of failing the batch.
+// Chains without Multicall3 get one eth_call per call.
+pub async fn aggregate<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    calls: Vec<(Address, Bytes)>,
+    block: Option<BlockId>,
+) -> Result<Vec<Option<Bytes>>, DappError> {
+    if calls.is_empty() {
+        return Ok(Vec::new())
+    }
+    let multicall = Multicall3::new(
+        MULTICALL3_ADDRESS.parse::<Address>().expect("valid Multicall3 address"),
+        Arc::clone(&client),
+    );
+    let batch = calls.iter()
+        .map(|(target, data)| (*target, true, data.clone()))
+        .collect();
+    let mut call = multicall.aggregate_3(batch);
+    if let Some(block) = block {
+        call = call.block(block);
+    }
+    match call.call().await {
+        Ok(results) => Ok(results.into_iter()
+            .map(|(success, data)| if success { Some(data) } else { None })
+            .collect()),
+        // no code at the address, the empty answer does not decode
+        Err(ContractError::DetokenizationError(_)) | Err(ContractError::AbiError(_)) => {
+            log::info!("Multicall3 not available, falling back to single calls");
+            aggregate_one_by_one(&client, calls, block).await
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn aggregate_one_by_one<P: JsonRpcClient>(
+    client: &Provider<P>,
+    calls: Vec<(Address, Bytes)>,
+    block: Option<BlockId>,
+) -> Result<Vec<Option<Bytes>>, DappError> {
+    let mut results = Vec::with_capacity(calls.len());
+    for (target, data) in calls {
+        let tx: TypedTransaction = Eip1559TransactionRequest::new().to(target).data(data).into();
+        match client.call(&tx, block).await {
+            Ok(result) => results.push(Some(result)),
+            Err(err) => match DappError::from(err) {
+                DappError::ContractRevert(_) => results.push(None),
+                err => return Err(err),
+            },
+        }
+    }
+    Ok(results)
+}
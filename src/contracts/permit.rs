@@ -0,0 +1,231 @@
+use ethers::{abi::{self, ParamType, Token}, contract::abigen, prelude::*, utils::keccak256};
+use ethers_core::types::transaction::eip712::EIP712Domain;
+use serde_json::json;
+use std::sync::Arc;
+use crate::{
+    contracts::{metadata::decode_uint, multicall::aggregate},
+    ethereum::{parse_typed_data, DappError, TypedData},
+};
+
+abigen!(
+    IERC20Permit,
+    "src/abis/erc20_permit.json"
+);
+
+// the struct of EIP-2612, DAI's permit has another one (holder, nonce, expiry, allowed)
+const PERMIT_TYPE: &str = "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+// tried in order when the token has no version() view
+const DEFAULT_VERSIONS: [&str; 2] = ["1", "2"];
+
+// EIP-712 domain of a token implementing permit, and the next nonce of the owner
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PermitSupport {
+    pub domain: EIP712Domain,
+    pub nonce: U256,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permit {
+    pub owner: Address,
+    pub spender: Address,
+    pub value: U256,
+    pub nonce: U256,
+    pub deadline: U256, // unix time in seconds
+}
+
+// the arguments of permit() besides the Permit fields
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PermitSignature {
+    pub v: u8,
+    pub r: H256,
+    pub s: H256,
+}
+
+impl From<Signature> for PermitSignature {
+    fn from(signature: Signature) -> Self {
+        let mut r = H256::zero();
+        let mut s = H256::zero();
+        signature.r.to_big_endian(r.as_bytes_mut());
+        signature.s.to_big_endian(s.as_bytes_mut());
+        // some wallets give the recovery id (0 or 1), the contracts expect 27 or 28
+        let v = if signature.v < 27 { signature.v + 27 } else { signature.v };
+        Self { v: v as u8, r, s }
+    }
+}
+
+// Checks the token answers DOMAIN_SEPARATOR() and nonces(owner), and finds the
+// domain the separator was built from out of name(), version() and the chain.
+// Fails with PermitUnsupported when the token has no EIP-2612 permit.
+pub async fn fetch_permit_support<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    token_address: Address,
+    owner: Address,
+) -> Result<PermitSupport, DappError> {
+    let chain_id = client.get_chainid().await?;
+    let token_contract = IERC20Permit::new(token_address, Arc::clone(&client));
+    let calls = [
+        token_contract.domain_separator().calldata(),
+        token_contract.nonces(owner).calldata(),
+        token_contract.name().calldata(),
+        token_contract.version().calldata(),
+        token_contract.permit_typehash().calldata(),
+    ];
+    let calls = calls.into_iter()
+        .map(|data| (token_address, data.unwrap_or_default()))
+        .collect();
+    let answers = aggregate(client, calls, None).await?;
+    let answer = |index: usize| answers.get(index).and_then(Option::as_ref).map(|data| data.as_ref());
+    let unsupported = |reason: &str| DappError::PermitUnsupported(reason.to_string());
+
+    let separator = answer(0)
+        .filter(|data| data.len() == 32)
+        .map(H256::from_slice)
+        .ok_or_else(|| unsupported("no DOMAIN_SEPARATOR()"))?;
+    let nonce = answer(1)
+        .and_then(decode_uint)
+        .ok_or_else(|| unsupported("no nonces(owner)"))?;
+    if let Some(typehash) = answer(4).filter(|data| data.len() == 32) {
+        if typehash != keccak256(PERMIT_TYPE) {
+            return Err(unsupported("its permit is not the EIP-2612 one (DAI style?)"))
+        }
+    }
+    let name = answer(2)
+        .and_then(decode_string)
+        .ok_or_else(|| unsupported("no name() to build the domain from"))?;
+    let versions: Vec<Option<String>> = match answer(3).and_then(decode_string) {
+        Some(version) => vec![Some(version)],
+        None => DEFAULT_VERSIONS.iter().map(|version| Some(version.to_string())).chain([None]).collect(),
+    };
+    versions.into_iter()
+        .map(|version| EIP712Domain {
+            name: Some(name.clone()),
+            version,
+            chain_id: Some(chain_id),
+            verifying_contract: Some(token_address),
+            salt: None,
+        })
+        .find(|domain| H256(domain.separator()) == separator)
+        .map(|domain| PermitSupport { domain, nonce })
+        .ok_or_else(|| unsupported("DOMAIN_SEPARATOR() does not match its name, version and chain"))
+}
+
+// exact, the domain hashes the name as it is
+fn decode_string(data: &[u8]) -> Option<String> {
+    match abi::decode(&[ParamType::String], data).ok()?.pop()? {
+        Token::String(text) => Some(text),
+        _ => None,
+    }
+}
+
+// The eth_signTypedData_v4 payload of permit, as sent to the wallet and parsed.
+pub fn permit_typed_data(domain: &EIP712Domain, permit: &Permit) -> Result<(String, TypedData), DappError> {
+    let mut domain_types = Vec::new();
+    let mut domain_values = serde_json::Map::new();
+    if let Some(name) = &domain.name {
+        domain_types.push(json!({ "name": "name", "type": "string" }));
+        domain_values.insert("name".to_string(), json!(name));
+    }
+    if let Some(version) = &domain.version {
+        domain_types.push(json!({ "name": "version", "type": "string" }));
+        domain_values.insert("version".to_string(), json!(version));
+    }
+    if let Some(chain_id) = domain.chain_id {
+        domain_types.push(json!({ "name": "chainId", "type": "uint256" }));
+        domain_values.insert("chainId".to_string(), json!(chain_id.as_u64()));
+    }
+    if let Some(verifying_contract) = domain.verifying_contract {
+        domain_types.push(json!({ "name": "verifyingContract", "type": "address" }));
+        domain_values.insert("verifyingContract".to_string(), json!(format!("{:?}", verifying_contract)));
+    }
+    let payload = json!({
+        "types": {
+            "EIP712Domain": domain_types,
+            "Permit": [
+                { "name": "owner", "type": "address" },
+                { "name": "spender", "type": "address" },
+                { "name": "value", "type": "uint256" },
+                { "name": "nonce", "type": "uint256" },
+                { "name": "deadline", "type": "uint256" },
+            ],
+        },
+        "primaryType": "Permit",
+        "domain": domain_values,
+        "message": {
+            "owner": format!("{:?}", permit.owner),
+            "spender": format!("{:?}", permit.spender),
+            "value": permit.value.to_string(),
+            "nonce": permit.nonce.to_string(),
+            "deadline": permit.deadline.to_string(),
+        },
+    })
+    .to_string();
+    let typed_data = parse_typed_data(&payload)?;
+    Ok((payload, typed_data))
+}
+
+// Anyone can submit the signed permit, from pays the gas.
+// client is expected to be a wallet backed provider (Provider<Eip1193>)
+pub async fn submit_permit<P: JsonRpcClient + 'static>(
+    client: Arc<Provider<P>>,
+    token_address: Address,
+    from: Address,
+    permit: &Permit,
+    signature: PermitSignature,
+) -> Result<H256, DappError> {
+    let token_contract = IERC20Permit::new(token_address, client);
+    let call = token_contract
+        .permit(permit.owner, permit.spender, permit.value, permit.deadline, signature.v, signature.r.0, signature.s.0)
+        .from(from);
+    let pending = call.send().await?;
+    Ok(pending.tx_hash())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::typed_data_hash;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    // USDC on mainnet
+    fn usdc_domain() -> EIP712Domain {
+        EIP712Domain {
+            name: Some("USD Coin".to_string()),
+            version: Some("2".to_string()),
+            chain_id: Some(1.into()),
+            verifying_contract: Some("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap()),
+            salt: None,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn hashes_the_permit() {
+        let domain = usdc_domain();
+        // what DOMAIN_SEPARATOR() answers
+        assert_eq!(
+            format!("{:?}", H256(domain.separator())),
+            "0x06c37168a7db5138defc7866392bb87a741f9b3d104deb5094588ce041cae335"
+        );
+        let permit = Permit {
+            owner: "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23".parse().unwrap(),
+            spender: domain.verifying_contract.unwrap(),
+            value: 1000.into(),
+            nonce: 3.into(),
+            deadline: 1_800_000_000u64.into(),
+        };
+        let (_, typed_data) = permit_typed_data(&domain, &permit).unwrap();
+        assert_eq!(
+            format!("{:?}", typed_data_hash(&typed_data).unwrap()),
+            "0x3bfba0ea6848a62da0721e362639f0b36c94051313508201e24f7e1a90130277"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn normalizes_v() {
+        for (v, expected) in [(0, 27), (1, 28), (27, 27), (28, 28)] {
+            let signature = PermitSignature::from(Signature { r: 1.into(), s: 2.into(), v });
+            assert_eq!(signature.v, expected);
+            assert_eq!(signature.r, H256::from_low_u64_be(1));
+            assert_eq!(signature.s, H256::from_low_u64_be(2));
+        }
+    }
+}
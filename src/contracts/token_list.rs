@@ -0,0 +1,215 @@
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::ethereum::DappError;
+
+// Bundled token list, edit src/tokenlist.json to offer other tokens.
+const TOKEN_LIST: &str = include_str!("../tokenlist.json");
+
+// limits of the tokenlists.org schema
+const MAX_LIST_NAME: usize = 30;
+const MAX_TOKENS: usize = 10_000;
+const MAX_TOKEN_NAME: usize = 40;
+const MAX_TOKEN_SYMBOL: usize = 20;
+// errors shown when a list does not validate
+const MAX_REPORTED_ERRORS: usize = 5;
+
+// Token list in the https://tokenlists.org JSON schema, only the fields the
+// dapp uses are kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenList {
+    pub name: String,
+    pub timestamp: String,
+    pub version: TokenListVersion,
+    pub tokens: Vec<TokenInfo>,
+    #[serde(default, rename = "logoURI")]
+    pub logo_uri: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenListVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub chain_id: u64,
+    pub address: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u64,
+    #[serde(default, rename = "logoURI")]
+    pub logo_uri: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl TokenInfo {
+    // case-insensitive search on the symbol, the name or the address
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || self.symbol.to_lowercase().contains(&query)
+            || self.name.to_lowercase().contains(&query)
+            || self.address.to_lowercase() == query
+    }
+}
+
+impl TokenList {
+    pub fn tokens_on(&self, chain_id: u64) -> impl Iterator<Item = &TokenInfo> {
+        self.tokens.iter().filter(move |token| token.chain_id == chain_id)
+    }
+
+    pub fn version_label(&self) -> String {
+        format!("v{}.{}.{}", self.version.major, self.version.minor, self.version.patch)
+    }
+}
+
+pub fn bundled_token_list() -> TokenList {
+    parse_token_list(TOKEN_LIST).expect("src/tokenlist.json is not a valid token list")
+}
+
+// Parses a token list and checks it against the constraints of the schema,
+// the error lists the first problems found.
+pub fn parse_token_list(json: &str) -> Result<TokenList, DappError> {
+    let list: TokenList = serde_json::from_str(json)
+        .map_err(|err| DappError::InvalidTokenList(err.to_string()))?;
+    let errors = validate(&list);
+    if errors.is_empty() {
+        return Ok(list)
+    }
+    let mut message = errors.iter()
+        .take(MAX_REPORTED_ERRORS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if errors.len() > MAX_REPORTED_ERRORS {
+        message.push_str(&format!(" and {} more", errors.len() - MAX_REPORTED_ERRORS));
+    }
+    Err(DappError::InvalidTokenList(message))
+}
+
+fn validate(list: &TokenList) -> Vec<String> {
+    let mut errors = Vec::new();
+    if list.name.trim().is_empty() || list.name.chars().count() > MAX_LIST_NAME {
+        errors.push(format!("the list name must have 1 to {} characters", MAX_LIST_NAME));
+    }
+    if list.tokens.is_empty() || list.tokens.len() > MAX_TOKENS {
+        errors.push(format!("the list must have 1 to {} tokens", MAX_TOKENS));
+    }
+    let mut seen = HashSet::new();
+    for (index, token) in list.tokens.iter().enumerate() {
+        let token_errors = validate_token(token);
+        if !token_errors.is_empty() {
+            errors.push(format!("token {} ({}): {}", index, token.symbol, token_errors.join(", ")));
+        }
+        if !seen.insert((token.chain_id, token.address.to_lowercase())) {
+            errors.push(format!("token {} ({}): listed twice on chain {}", index, token.symbol, token.chain_id));
+        }
+    }
+    errors
+}
+
+fn validate_token(token: &TokenInfo) -> Vec<String> {
+    let mut errors = Vec::new();
+    if token.chain_id == 0 {
+        errors.push("chainId must be at least 1".to_string());
+    }
+    // the schema asks for 0x and 40 hex digits, any checksum
+    let hex = token.address.strip_prefix("0x").unwrap_or_default();
+    if hex.len() != 40 || token.address.parse::<Address>().is_err() {
+        errors.push(format!("\"{}\" is not an address", token.address));
+    }
+    if token.name.is_empty() || token.name.chars().count() > MAX_TOKEN_NAME {
+        errors.push(format!("name must have 1 to {} characters", MAX_TOKEN_NAME));
+    }
+    if token.symbol.is_empty() || token.symbol.chars().count() > MAX_TOKEN_SYMBOL {
+        errors.push(format!("symbol must have 1 to {} characters", MAX_TOKEN_SYMBOL));
+    }
+    if token.decimals > 255 {
+        errors.push("decimals must be at most 255".to_string());
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn token(chain_id: u64, address: &str, symbol: &str) -> Value {
+        json!({ "chainId": chain_id, "address": address, "name": symbol, "symbol": symbol, "decimals": 18 })
+    }
+
+    fn list(tokens: Vec<Value>) -> String {
+        json!({
+            "name": "Test list",
+            "timestamp": "2024-01-01T00:00:00.000Z",
+            "version": { "major": 1, "minor": 2, "patch": 3 },
+            "tokens": tokens,
+        }).to_string()
+    }
+
+    fn error(json: &str) -> String {
+        match parse_token_list(json) {
+            Err(DappError::InvalidTokenList(message)) => message,
+            other => panic!("expected an invalid list, got {:?}", other),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn parses_valid_list() {
+        let list = parse_token_list(&list(vec![
+            token(1, "0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI"),
+            token(5, "0x6b175474e89094c44da98b954eedeac495271d0f", "DAI"),
+        ])).unwrap();
+        assert_eq!(list.version_label(), "v1.2.3");
+        assert_eq!(list.tokens_on(5).count(), 1);
+        assert!(list.keywords.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn parses_bundled_list() {
+        assert!(bundled_token_list().tokens_on(1).count() > 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_invalid_json() {
+        error("{\"name\": \"Test list\"}");
+        error("not json");
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_invalid_tokens() {
+        let message = error(&list(vec![
+            token(0, "0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI"),
+            token(1, "6B175474E89094C44Da98b954EedeAC495271d0F", ""),
+        ]));
+        assert_eq!(message, "token 0 (DAI): chainId must be at least 1, \
+            token 1 (): \"6B175474E89094C44Da98b954EedeAC495271d0F\" is not an address, \
+            name must have 1 to 40 characters, symbol must have 1 to 20 characters");
+        assert_eq!(error(&list(Vec::new())), "the list must have 1 to 10000 tokens");
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_tokens_listed_twice() {
+        let message = error(&list(vec![
+            token(1, "0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI"),
+            token(1, "0x6b175474e89094c44da98b954eedeac495271d0f", "DAI2"),
+        ]));
+        assert_eq!(message, "token 1 (DAI2): listed twice on chain 1");
+    }
+
+    #[wasm_bindgen_test]
+    fn reports_the_first_errors_only() {
+        let tokens = (0..7).map(|_| token(0, "0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI")).collect();
+        assert!(error(&list(tokens)).ends_with(" and 8 more"));
+    }
+}
@@ -0,0 +1,124 @@
+use ethers::prelude::*;
+use std::fmt;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DappError {
+    InvalidAddress(String), // the rejected input
+    NotAContract(String), // address without code
+    Transport(String),
+    ContractRevert(String),
+    WalletRejected,
+    WrongChain { expected: u64, actual: u64 },
+    Decoding(String), // answer of the network or the wallet that can't be read
+    InvalidInput(String), // what is wrong with a value typed by the user
+    InvalidHex(String), // the rejected input
+    InvalidSignature(String),
+    InvalidTypedData(String),
+    InvalidSiwe(String),
+    InvalidTokenList(String),
+    PermitUnsupported(String), // why the token can't be used with permit
+    InvalidEnsName(String), // the rejected input
+    EnsNotFound(String), // name without an address
+    EnsUnavailable, // no ENS registry on the network
+    MissingAddress(String), // the field left empty
+}
+
+// messages are shown as they are to the user
+impl fmt::Display for DappError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DappError::InvalidAddress(input) => write!(f, "\"{}\" is not a valid address", input),
+            DappError::NotAContract(address) => write!(f, "There is no contract at {}", address),
+            DappError::Transport(err) => write!(f, "Could not reach the network: {}", err),
+            DappError::ContractRevert(reason) => write!(f, "The contract reverted: {}", reason),
+            DappError::WalletRejected => write!(f, "The request was rejected in the wallet"),
+            DappError::WrongChain { expected, actual } => write!(
+                f, "The wallet is connected to chain {}, switch it to chain {}", actual, expected
+            ),
+            DappError::Decoding(err) => write!(f, "Unexpected response: {}", err),
+            DappError::InvalidInput(err) => write!(f, "Invalid input: {}", err),
+            DappError::InvalidHex(input) => write!(f, "\"{}\" is not hex data", input),
+            DappError::InvalidSignature(err) => write!(f, "Invalid signature: {}", err),
+            DappError::InvalidTypedData(err) => write!(f, "Invalid typed data: {}", err),
+            DappError::InvalidSiwe(err) => write!(f, "Invalid sign-in message: {}", err),
+            DappError::InvalidTokenList(err) => write!(f, "Invalid token list: {}", err),
+            DappError::PermitUnsupported(reason) => write!(f, "The token does not support permit: {}", reason),
+            DappError::InvalidEnsName(input) => write!(f, "\"{}\" is not an address or an ENS name", input),
+            DappError::EnsNotFound(name) => write!(f, "{} does not resolve to an address", name),
+            DappError::EnsUnavailable => write!(f, "ENS is not available on this network"),
+            DappError::MissingAddress(field) => write!(f, "Enter the {} address or ENS name", field),
+        }
+    }
+}
+
+impl std::error::Error for DappError {}
+
+impl DappError {
+    // JSON-RPC error codes, EIP-1193 ones included
+    pub fn from_rpc(code: i64, message: &str) -> Self {
+        match code {
+            4001 => DappError::WalletRejected,
+            3 => DappError::ContractRevert(message.to_owned()),
+            // nodes also report reverts as generic server errors
+            _ if message.contains("revert") => DappError::ContractRevert(message.to_owned()),
+            _ => DappError::Transport(message.to_owned()),
+        }
+    }
+
+    pub fn parse_address(input: &str) -> Result<Address, DappError> {
+        input.trim()
+            .parse::<Address>()
+            .map_err(|_| DappError::InvalidAddress(input.to_owned()))
+    }
+}
+
+// Error object of a failed wallet request (see Eip1193 transport)
+#[derive(Clone, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<serde_json::Value>,
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}) {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl From<RpcError> for ProviderError {
+    fn from(err: RpcError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(err))
+    }
+}
+
+impl From<ProviderError> for DappError {
+    fn from(err: ProviderError) -> Self {
+        match err {
+            ProviderError::JsonRpcClientError(err) => {
+                if let Some(err) = err.downcast_ref::<RpcError>() {
+                    DappError::from_rpc(err.code, &err.message)
+                } else if let Some(WsClientError::JsonRpcError(err)) = err.downcast_ref::<WsClientError>() {
+                    DappError::from_rpc(err.code, &err.message)
+                } else {
+                    DappError::Transport(err.to_string())
+                }
+            }
+            ProviderError::SerdeJson(err) => DappError::Decoding(err.to_string()),
+            ProviderError::HexError(err) => DappError::Decoding(err.to_string()),
+            err => DappError::Transport(err.to_string()),
+        }
+    }
+}
+
+impl<P: JsonRpcClient> From<ContractError<Provider<P>>> for DappError {
+    fn from(err: ContractError<Provider<P>>) -> Self {
+        match err {
+            ContractError::MiddlewareError(err) | ContractError::ProviderError(err) => err.into(),
+            // an empty answer usually means there is no contract at that address
+            err => DappError::Decoding(err.to_string()),
+        }
+    }
+}
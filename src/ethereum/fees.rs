@@ -0,0 +1,75 @@
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::ethereum::DappError;
+
+// blocks looked back by eth_feeHistory and the reward percentiles asked for each
+// speed, in the same order as FeeSpeed::ALL
+const HISTORY_BLOCKS: u64 = 10;
+const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeeSpeed {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl FeeSpeed {
+    pub const ALL: [FeeSpeed; 3] = [FeeSpeed::Slow, FeeSpeed::Normal, FeeSpeed::Fast];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FeeSpeed::Slow => "Slow",
+            FeeSpeed::Normal => "Normal",
+            FeeSpeed::Fast => "Fast",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fee {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeSuggestions {
+    pub base_fee: U256, // of the next block
+    pub fees: [Fee; 3], // indexed like FeeSpeed::ALL
+}
+
+impl FeeSuggestions {
+    pub fn get(&self, speed: FeeSpeed) -> Fee {
+        let index = FeeSpeed::ALL.iter().position(|s| *s == speed).unwrap_or_default();
+        self.fees[index]
+    }
+}
+
+// EIP-1559 fees from the recent blocks: the tip is the average reward paid at each
+// percentile, the max fee leaves room for the base fee to double.
+pub async fn suggest_fees<P: JsonRpcClient>(client: &Provider<P>) -> Result<FeeSuggestions, DappError> {
+    let history = client.fee_history(HISTORY_BLOCKS, BlockNumber::Latest, &REWARD_PERCENTILES).await?;
+    // the last entry is the base fee of the upcoming block
+    let base_fee = history.base_fee_per_gas.last().copied()
+        .ok_or_else(|| DappError::Decoding("empty fee history, is EIP-1559 enabled?".to_string()))?;
+    let mut fees = [Fee::default(); 3];
+    for (index, fee) in fees.iter_mut().enumerate() {
+        let rewards: Vec<U256> = history.reward.iter()
+            .filter_map(|block| block.get(index).copied())
+            .collect();
+        let tip = if rewards.is_empty() {
+            U256::zero()
+        } else {
+            rewards.iter().fold(U256::zero(), |sum, reward| sum + reward) / rewards.len()
+        };
+        *fee = Fee {
+            max_fee_per_gas: base_fee * 2 + tip,
+            max_priority_fee_per_gas: tip,
+        };
+    }
+    Ok(FeeSuggestions { base_fee, fees })
+}
+
+pub async fn estimate_gas<P: JsonRpcClient>(client: &Provider<P>, tx: &Eip1559TransactionRequest) -> Result<U256, DappError> {
+    Ok(client.estimate_gas(&tx.clone().into()).await?)
+}
@@ -0,0 +1,57 @@
+use ethers::prelude::*;
+use crate::ethereum::DappError;
+
+// blocks per eth_getLogs request, public nodes cap the range or the number of results
+pub const LOG_CHUNK: u64 = 5_000;
+// smallest range tried when a node keeps refusing a request
+const MIN_LOG_CHUNK: u64 = 100;
+
+// eth_getLogs over [from_block, to_block] split in chunks, the chunk is halved
+// whenever the node rejects it. on_progress gets the last block scanned.
+pub async fn get_logs_chunked<P: JsonRpcClient>(
+    client: &Provider<P>,
+    filter: &Filter,
+    from_block: u64,
+    to_block: u64,
+    on_progress: impl Fn(u64),
+) -> Result<Vec<Log>, DappError> {
+    let mut logs = Vec::new();
+    let mut chunk = LOG_CHUNK;
+    let mut start = from_block;
+    while start <= to_block {
+        let end = (start + chunk - 1).min(to_block);
+        let range = filter.clone().from_block(start).to_block(end);
+        match client.get_logs(&range).await {
+            Ok(mut found) => {
+                logs.append(&mut found);
+                on_progress(end);
+                start = end + 1;
+            }
+            Err(err) if chunk > MIN_LOG_CHUNK => {
+                log::warn!("eth_getLogs {}..{} failed, retrying smaller ranges: {}", start, end, err);
+                chunk /= 2;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(logs)
+}
+
+// fills the ends of a block range left blank by the user: to defaults to the
+// chain head, from to `blocks` blocks before it
+pub async fn block_range<P: JsonRpcClient>(
+    client: &Provider<P>,
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+    blocks: u64,
+) -> Result<(u64, u64), DappError> {
+    let to_block = match to_block {
+        Some(block) => block,
+        None => client.get_block_number().await?.as_u64(),
+    };
+    let from_block = from_block.unwrap_or_else(|| to_block.saturating_sub(blocks.saturating_sub(1)));
+    if from_block > to_block {
+        return Err(DappError::InvalidInput(format!("block {} is after block {}", from_block, to_block)))
+    }
+    Ok((from_block, to_block))
+}
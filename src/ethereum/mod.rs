@@ -0,0 +1,27 @@
+// Network side of the dapp: errors, configured networks, the shared websocket
+// provider and the read queries made through it. Nothing here depends on yew.
+mod errors;
+mod fees;
+mod logs;
+mod networks;
+mod provider;
+mod queries;
+mod receipts;
+mod revert;
+mod signing;
+mod siwe;
+mod subscriptions;
+mod typed_data;
+
+pub use errors::{DappError, RpcError};
+pub use fees::{estimate_gas, suggest_fees, Fee, FeeSpeed, FeeSuggestions};
+pub use logs::{block_range, get_logs_chunked, LOG_CHUNK};
+pub use networks::{NetworkConfig, load_networks, find_network, parse_chain_id};
+pub use provider::{ProviderHandle, WsProvider};
+pub use queries::{get_native_balance, ensure_chain};
+pub use receipts::{watch_transaction, TxStatus, WatchOptions};
+pub use revert::{decode_revert, replay_revert, RevertReason};
+pub use signing::{message_bytes, parse_signature, recover_signer, MessageFormat};
+pub use siwe::{siwe_time, SiweMessage};
+pub use subscriptions::{watch_blocks, watch_transfers};
+pub use typed_data::{describe_typed_data, parse_typed_data, recover_typed_data_signer, typed_data_hash, TypedData, TypedField};
@@ -0,0 +1,48 @@
+use serde::Deserialize;
+
+// Bundled list of supported networks, edit src/networks.json to target other chains.
+const NETWORKS: &str = include_str!("../networks.json");
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct NetworkConfig {
+    pub chain_id: u64,
+    pub name: String,
+    pub native_symbol: String,
+    pub decimals: u32,
+    pub rpc_urls: Vec<String>, // websocket endpoints, tried in order
+    #[serde(default)]
+    pub http_rpc_urls: Vec<String>, // given to the wallet when it doesn't know the chain
+    pub explorer_url: Option<String>,
+}
+
+impl NetworkConfig {
+    // chain id as the wallet reports it ("0xaa36a7")
+    pub fn chain_id_hex(&self) -> String {
+        format!("{:#x}", self.chain_id)
+    }
+
+    pub fn explorer_tx(&self, hash: &str) -> Option<String> {
+        self.explorer_url
+            .as_ref()
+            .map(|url| format!("{}/tx/{}", url, hash))
+    }
+
+    pub fn explorer_token(&self, address: &str) -> Option<String> {
+        self.explorer_url
+            .as_ref()
+            .map(|url| format!("{}/token/{}", url, address))
+    }
+}
+
+pub fn load_networks() -> Vec<NetworkConfig> {
+    serde_json::from_str(NETWORKS).expect("src/networks.json is not a valid network list")
+}
+
+pub fn find_network(networks: &[NetworkConfig], chain_id: u64) -> Option<&NetworkConfig> {
+    networks.iter().find(|network| network.chain_id == chain_id)
+}
+
+// wallets report the chain id as an hex string
+pub fn parse_chain_id(chain: &str) -> Option<u64> {
+    u64::from_str_radix(chain.trim_start_matches("0x"), 16).ok()
+}
@@ -0,0 +1,122 @@
+use ethers::prelude::*;
+use futures::future::{Future, FutureExt, LocalBoxFuture, Shared};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc, sync::Arc};
+use ws_stream_wasm::WsMeta;
+use crate::ethereum::{DappError, NetworkConfig};
+
+pub type WsProvider = Arc<Provider<Ws>>;
+
+type Connection = Shared<LocalBoxFuture<'static, Result<WsProvider, DappError>>>;
+// primary name of an address on the network, see lookup_ens_name
+type NameLookup = Shared<LocalBoxFuture<'static, Result<Option<String>, DappError>>>;
+
+// Single websocket per selected network, shared through the yew context.
+// Every clone points to the same connection, which is opened lazily and
+// re-opened when the socket drops, and to the same name lookups.
+#[derive(Clone)]
+pub struct ProviderHandle {
+    network: NetworkConfig,
+    connection: Rc<RefCell<Option<Connection>>>,
+    names: Rc<RefCell<HashMap<Address, NameLookup>>>, // in flight or answered
+}
+
+impl fmt::Debug for ProviderHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProviderHandle")
+            .field("network", &self.network.name)
+            .finish()
+    }
+}
+
+impl PartialEq for ProviderHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.connection, &other.connection)
+    }
+}
+
+impl ProviderHandle {
+    pub fn new(network: NetworkConfig) -> Self {
+        Self {
+            network,
+            connection: Rc::new(RefCell::new(None)),
+            names: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    pub fn network(&self) -> &NetworkConfig {
+        &self.network
+    }
+
+    pub async fn get(&self) -> Result<WsProvider, DappError> {
+        // a dropped socket is retried once, a failed connect is reported to the caller
+        let mut retried = false;
+        loop {
+            let connection = self.connection();
+            match connection.clone().await {
+                Ok(provider) if provider.as_ref().as_ref().ready() => return Ok(provider),
+                Ok(_) if !retried => {
+                    log::warn!("Websocket to {} dropped, reconnecting", self.network.name);
+                    self.forget(&connection);
+                    retried = true;
+                }
+                Ok(_) => {
+                    self.forget(&connection);
+                    return Err(DappError::Transport(format!("lost connection to {}", self.network.name)))
+                }
+                Err(err) => {
+                    self.forget(&connection);
+                    return Err(err)
+                }
+            }
+        }
+    }
+
+    // in-flight or established connection, shared by concurrent callers
+    fn connection(&self) -> Connection {
+        let mut cached = self.connection.borrow_mut();
+        cached
+            .get_or_insert_with(|| connect(self.network.clone()).boxed_local().shared())
+            .clone()
+    }
+
+    // Name of address from the lookup already made or in flight on this network,
+    // lookup is only started by the first caller. Failures are not kept.
+    pub async fn lookup_name<F>(&self, address: Address, lookup: impl FnOnce() -> F) -> Result<Option<String>, DappError>
+    where
+        F: Future<Output = Result<Option<String>, DappError>> + 'static,
+    {
+        let pending = self.names.borrow_mut()
+            .entry(address)
+            .or_insert_with(|| lookup().boxed_local().shared())
+            .clone();
+        let name = pending.clone().await;
+        if name.is_err() {
+            let mut names = self.names.borrow_mut();
+            if names.get(&address).is_some_and(|current| current.ptr_eq(&pending)) {
+                names.remove(&address);
+            }
+        }
+        name
+    }
+
+    fn forget(&self, connection: &Connection) {
+        let mut cached = self.connection.borrow_mut();
+        if cached.as_ref().is_some_and(|current| current.ptr_eq(connection)) {
+            *cached = None;
+        }
+    }
+}
+
+async fn connect(network: NetworkConfig) -> Result<WsProvider, DappError> {
+    for url in &network.rpc_urls {
+        // Ws::connect throws on failure, so the socket is opened here
+        match WsMeta::connect(url, None).await {
+            Ok((_, stream)) => {
+                log::info!("Connected to {} through {}", network.name, url);
+                return Ok(Arc::new(Provider::new(Ws::new(stream))))
+            }
+            Err(err) => log::warn!("Could not connect to {}: {}", url, err),
+        }
+    }
+    Err(DappError::Transport(format!("could not connect to {}", network.name)))
+}
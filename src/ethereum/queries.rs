@@ -0,0 +1,20 @@
+use ethers::prelude::*;
+use std::sync::Arc;
+use crate::ethereum::DappError;
+
+// balance and the block it was read at
+pub async fn get_native_balance(client: Arc<Provider<Ws>>, address: String) -> Result<(U256, u64), DappError>{
+    let address = DappError::parse_address(&address)?;
+    let block = client.get_block_number().await?;
+    let balance = client.get_balance(address, Some(BlockId::Number(block.into()))).await?;
+    Ok((balance, block.as_u64()))
+}
+
+// fails with WrongChain if the client is not on the expected chain
+pub async fn ensure_chain<P: JsonRpcClient>(client: &Provider<P>, expected: u64) -> Result<(), DappError> {
+    let actual = client.get_chainid().await?.as_u64();
+    if actual != expected {
+        return Err(DappError::WrongChain { expected, actual })
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use ethers::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+use std::time::Duration;
+use crate::ethereum::{DappError, ProviderHandle};
+
+// polls without the sender nonce moving before calling a known transaction dropped
+const DROPPED_AFTER_POLLS: u32 = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchOptions {
+    pub confirmations: u64, // blocks on top of the inclusion one, 1 = mined
+    pub timeout: Duration,
+    pub min_interval: Duration, // first poll delay, doubled while nothing changes
+    pub max_interval: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            confirmations: 1,
+            timeout: Duration::from_secs(600),
+            min_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TxStatus {
+    Pending,
+    Mined { block: u64, confirmations: u64 }, // waiting for more confirmations
+    Confirmed(Box<TransactionReceipt>),
+    Failed(Box<TransactionReceipt>), // mined but reverted
+    Dropped,
+    Replaced, // another transaction used its nonce
+    TimedOut,
+}
+
+impl TxStatus {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, TxStatus::Pending | TxStatus::Mined { .. })
+    }
+}
+
+// sender and nonce, once the node has seen the transaction
+struct Seen {
+    from: Address,
+    nonce: U256,
+    missing_polls: u32,
+}
+
+// Follows a transaction until it is confirmed, fails, disappears or the timeout
+// expires. Every status change is reported to on_progress, the last one is returned.
+// Network errors are logged and retried on the next poll.
+pub async fn watch_transaction(
+    provider: ProviderHandle,
+    tx_hash: H256,
+    options: WatchOptions,
+    on_progress: impl Fn(TxStatus),
+) -> TxStatus {
+    let started = js_sys::Date::now();
+    let mut interval = options.min_interval;
+    let mut seen: Option<Seen> = None;
+    let mut last = TxStatus::Pending;
+    on_progress(last.clone());
+    loop {
+        match poll(&provider, tx_hash, &options, &mut seen).await {
+            Ok(status) => {
+                if status != last {
+                    interval = options.min_interval;
+                    last = status;
+                    on_progress(last.clone());
+                    if last.is_final() {
+                        return last
+                    }
+                } else {
+                    interval = (interval * 2).min(options.max_interval);
+                }
+            }
+            Err(err) => {
+                log::warn!("Checking {:?} failed: {}", tx_hash, err);
+                interval = (interval * 2).min(options.max_interval);
+            }
+        }
+        if js_sys::Date::now() - started > options.timeout.as_millis() as f64 {
+            on_progress(TxStatus::TimedOut);
+            return TxStatus::TimedOut
+        }
+        TimeoutFuture::new(interval.as_millis() as u32).await;
+    }
+}
+
+async fn poll(
+    provider: &ProviderHandle,
+    tx_hash: H256,
+    options: &WatchOptions,
+    seen: &mut Option<Seen>,
+) -> Result<TxStatus, DappError> {
+    let client = provider.get().await?;
+    if let Some(receipt) = client.get_transaction_receipt(tx_hash).await? {
+        let block = match receipt.block_number {
+            Some(block) => block.as_u64(),
+            None => return Ok(TxStatus::Pending),
+        };
+        if receipt.status == Some(U64::zero()) {
+            return Ok(TxStatus::Failed(Box::new(receipt)))
+        }
+        let head = client.get_block_number().await?.as_u64();
+        let confirmations = head.saturating_sub(block) + 1;
+        if confirmations >= options.confirmations {
+            return Ok(TxStatus::Confirmed(Box::new(receipt)))
+        }
+        return Ok(TxStatus::Mined { block, confirmations })
+    }
+    if let Some(tx) = client.get_transaction(tx_hash).await? {
+        *seen = Some(Seen { from: tx.from, nonce: tx.nonce, missing_polls: 0 });
+        return Ok(TxStatus::Pending)
+    }
+    // unknown to the node: still propagating, dropped or replaced
+    match seen {
+        Some(seen) => {
+            let mined_nonce = client.get_transaction_count(seen.from, None).await?;
+            if mined_nonce > seen.nonce {
+                return Ok(TxStatus::Replaced)
+            }
+            seen.missing_polls += 1;
+            if seen.missing_polls >= DROPPED_AFTER_POLLS {
+                return Ok(TxStatus::Dropped)
+            }
+            Ok(TxStatus::Pending)
+        }
+        None => Ok(TxStatus::Pending),
+    }
+}
@@ -0,0 +1,163 @@
+use ethers::{abi::{self, Abi, ParamType, Token}, prelude::*, types::transaction::eip2718::TypedTransaction};
+use std::fmt;
+use crate::ethereum::{DappError, RpcError};
+
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0]; // Error(string)
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71]; // Panic(uint256)
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RevertReason {
+    Error(String), // require / revert with a message
+    Panic(U256),
+    Custom { name: String, args: Vec<String> }, // error declared in the contract ABI
+    Unknown(Bytes), // raw revert data, empty for a bare revert()
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevertReason::Error(message) => write!(f, "{}", message),
+            RevertReason::Panic(code) => write!(f, "panic {:#04x}: {}", code.low_u64(), panic_message(*code)),
+            RevertReason::Custom { name, args } => write!(f, "{}({})", name, args.join(", ")),
+            RevertReason::Unknown(data) if data.is_empty() => write!(f, "reverted without a reason"),
+            RevertReason::Unknown(data) => write!(f, "unknown error {}", data),
+        }
+    }
+}
+
+// panic codes emitted by solidity >= 0.8
+fn panic_message(code: U256) -> &'static str {
+    match code.low_u64() {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "corrupted storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to an uninitialized function",
+        _ => "unknown panic",
+    }
+}
+
+pub fn decode_revert(data: &[u8], abi: Option<&Abi>) -> RevertReason {
+    if data.len() < 4 {
+        return RevertReason::Unknown(data.to_vec().into())
+    }
+    let (selector, args) = data.split_at(4);
+    if selector == ERROR_SELECTOR {
+        if let Ok(Some(Token::String(message))) = abi::decode(&[ParamType::String], args)
+            .map(|tokens| tokens.into_iter().next())
+        {
+            return RevertReason::Error(message)
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Ok(Some(Token::Uint(code))) = abi::decode(&[ParamType::Uint(256)], args)
+            .map(|tokens| tokens.into_iter().next())
+        {
+            return RevertReason::Panic(code)
+        }
+    }
+    let custom = abi.into_iter()
+        .flat_map(|abi| abi.errors())
+        .filter(|error| error.signature()[..4] == *selector)
+        .find_map(|error| {
+            let tokens = error.decode(args).ok()?;
+            Some(RevertReason::Custom {
+                name: error.name.clone(),
+                args: tokens.iter().map(|token| token.to_string()).collect(),
+            })
+        });
+    custom.unwrap_or_else(|| RevertReason::Unknown(data.to_vec().into()))
+}
+
+// revert data attached to a failed eth_call, nodes send it as a hex string,
+// sometimes wrapped in an object
+fn revert_data(err: &ProviderError) -> Option<Bytes> {
+    let data = match err {
+        ProviderError::JsonRpcClientError(err) => {
+            if let Some(err) = err.downcast_ref::<RpcError>() {
+                err.data.clone()
+            } else if let Some(WsClientError::JsonRpcError(err)) = err.downcast_ref::<WsClientError>() {
+                err.data.clone()
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }?;
+    let hex = match &data {
+        serde_json::Value::String(hex) => hex.as_str(),
+        serde_json::Value::Object(object) => object.get("data")?.as_str()?,
+        _ => return None,
+    };
+    hex.parse().ok()
+}
+
+// Replays a mined transaction as a call on the state of its block to recover
+// why it reverted. None if the call does not revert anymore.
+pub async fn replay_revert<P: JsonRpcClient>(
+    client: &Provider<P>,
+    receipt: &TransactionReceipt,
+    abi: Option<&Abi>,
+) -> Result<Option<RevertReason>, DappError> {
+    let tx = client.get_transaction(receipt.transaction_hash).await?
+        .ok_or_else(|| DappError::Transport("transaction not found".to_string()))?;
+    let call: TypedTransaction = (&tx).into();
+    let block = receipt.block_number.map(|block| BlockId::Number(block.into()));
+    match client.call(&call, block).await {
+        Ok(_) => Ok(None),
+        Err(err) => match revert_data(&err) {
+            Some(data) => Ok(Some(decode_revert(&data, abi))),
+            None => match DappError::from(err) {
+                DappError::ContractRevert(message) => Ok(Some(RevertReason::Error(message))),
+                err => Err(err),
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::id;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn encoded(selector: [u8; 4], tokens: &[Token]) -> Vec<u8> {
+        [&selector[..], &abi::encode(tokens)].concat()
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_error_string() {
+        let data = encoded(ERROR_SELECTOR, &[Token::String("ERC20: transfer amount exceeds balance".to_string())]);
+        assert_eq!(decode_revert(&data, None), RevertReason::Error("ERC20: transfer amount exceeds balance".to_string()));
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_panic() {
+        let data = encoded(PANIC_SELECTOR, &[Token::Uint(0x11.into())]);
+        let reason = decode_revert(&data, None);
+        assert_eq!(reason, RevertReason::Panic(0x11.into()));
+        assert_eq!(reason.to_string(), "panic 0x11: arithmetic overflow or underflow");
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_custom_error_of_the_abi() {
+        let abi: Abi = serde_json::from_str(r#"[{"type":"error","name":"InsufficientBalance","inputs":[
+            {"name":"available","type":"uint256"},{"name":"required","type":"uint256"}]}]"#).unwrap();
+        let data = encoded(id("InsufficientBalance(uint256,uint256)"), &[Token::Uint(1.into()), Token::Uint(2.into())]);
+        let reason = decode_revert(&data, Some(&abi));
+        assert_eq!(reason.to_string(), "InsufficientBalance(1, 2)");
+        assert_eq!(decode_revert(&data, None), RevertReason::Unknown(data.into()));
+    }
+
+    #[wasm_bindgen_test]
+    fn keeps_unknown_data() {
+        assert_eq!(decode_revert(&[], None).to_string(), "reverted without a reason");
+        assert_eq!(decode_revert(&[0x08, 0xc3], None), RevertReason::Unknown(vec![0x08, 0xc3].into()));
+        // the selector of Error(string) without a string after it
+        assert_eq!(decode_revert(&ERROR_SELECTOR, None), RevertReason::Unknown(ERROR_SELECTOR.to_vec().into()));
+    }
+}
@@ -0,0 +1,69 @@
+use ethers::{prelude::*, utils::hex};
+use std::str::FromStr;
+use crate::ethereum::DappError;
+
+// how the message typed by the user is turned into the signed bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageFormat {
+    Text, // utf-8
+    Hex,  // 0x prefixed or not
+}
+
+impl MessageFormat {
+    pub const ALL: [MessageFormat; 2] = [MessageFormat::Text, MessageFormat::Hex];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MessageFormat::Text => "Text",
+            MessageFormat::Hex => "Hex",
+        }
+    }
+}
+
+pub fn message_bytes(input: &str, format: MessageFormat) -> Result<Vec<u8>, DappError> {
+    match format {
+        MessageFormat::Text => Ok(input.as_bytes().to_vec()),
+        MessageFormat::Hex => {
+            let input = input.trim();
+            hex::decode(input.strip_prefix("0x").unwrap_or(input))
+                .map_err(|_| DappError::InvalidHex(input.to_owned()))
+        }
+    }
+}
+
+pub fn parse_signature(input: &str) -> Result<Signature, DappError> {
+    Signature::from_str(input.trim())
+        .map_err(|err| DappError::InvalidSignature(err.to_string()))
+}
+
+// Address that signed message through personal_sign (EIP-191 prefixed hash).
+pub fn recover_signer(message: &[u8], signature: &Signature) -> Result<Address, DappError> {
+    signature.recover(message.to_vec())
+        .map_err(|err| DappError::InvalidSignature(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn decodes_hex() {
+        assert_eq!(message_bytes("0xdeadbeef", MessageFormat::Hex), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(message_bytes(" deadBEEF\n", MessageFormat::Hex), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(message_bytes("0x", MessageFormat::Hex), Ok(vec![]));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_malformed_hex() {
+        for input in ["0xabc", "0xzz", "hello"] {
+            assert_eq!(message_bytes(input, MessageFormat::Hex), Err(DappError::InvalidHex(input.to_string())));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn keeps_text_as_is() {
+        assert_eq!(message_bytes(" 0xdeadbeef ", MessageFormat::Text), Ok(b" 0xdeadbeef ".to_vec()));
+        assert_eq!(message_bytes("héllo", MessageFormat::Text), Ok("héllo".as_bytes().to_vec()));
+    }
+}
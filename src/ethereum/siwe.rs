@@ -0,0 +1,275 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use ethers::{prelude::*, utils::to_checksum};
+use std::{fmt, str::FromStr};
+use crate::ethereum::{recover_signer, DappError};
+
+const PREAMBLE: &str = " wants you to sign in with your Ethereum account:";
+const URI_TAG: &str = "URI: ";
+const VERSION_TAG: &str = "Version: ";
+const CHAIN_TAG: &str = "Chain ID: ";
+const NONCE_TAG: &str = "Nonce: ";
+const ISSUED_AT_TAG: &str = "Issued At: ";
+const EXPIRATION_TAG: &str = "Expiration Time: ";
+const NOT_BEFORE_TAG: &str = "Not Before: ";
+const REQUEST_ID_TAG: &str = "Request ID: ";
+const RESOURCES_TAG: &str = "Resources:";
+const MIN_NONCE: usize = 8;
+
+// Sign-In with Ethereum message (EIP-4361). Times are kept as written, the
+// signature is over the text so they can't be reformatted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SiweMessage {
+    pub domain: String, // host (and port) asking for the sign in
+    pub address: Address,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: String, // RFC 3339
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+// RFC 3339 time as used in the messages, from milliseconds since the epoch
+pub fn siwe_time(millis: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn parse_time(field: &str, time: &str) -> Result<i64, DappError> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.timestamp_millis())
+        .map_err(|_| invalid(format!("{} \"{}\" is not an RFC 3339 time", field, time)))
+}
+
+fn invalid(message: String) -> DappError {
+    DappError::InvalidSiwe(message)
+}
+
+impl SiweMessage {
+    // Checks the fields and that the message can be used at now (milliseconds
+    // since the epoch), the signature is checked by verify.
+    pub fn validate(&self, now: i64) -> Result<(), DappError> {
+        if self.domain.is_empty() || self.domain.contains(char::is_whitespace) {
+            return Err(invalid(format!("\"{}\" is not a valid domain", self.domain)))
+        }
+        if self.statement.as_deref().is_some_and(|statement| statement.contains('\n')) {
+            return Err(invalid("the statement must fit on one line".to_string()))
+        }
+        if !self.uri.contains(':') {
+            return Err(invalid(format!("\"{}\" is not an URI", self.uri)))
+        }
+        if self.version != "1" {
+            return Err(invalid(format!("unsupported version {}", self.version)))
+        }
+        if self.nonce.len() < MIN_NONCE || !self.nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid(format!("the nonce must be at least {} letters or digits", MIN_NONCE)))
+        }
+        if let Some(resource) = self.resources.iter().find(|resource| !resource.contains(':')) {
+            return Err(invalid(format!("\"{}\" is not an URI", resource)))
+        }
+        parse_time("Issued At", &self.issued_at)?;
+        if let Some(expiration) = &self.expiration_time {
+            if parse_time("Expiration Time", expiration)? <= now {
+                return Err(invalid(format!("the message expired at {}", expiration)))
+            }
+        }
+        if let Some(not_before) = &self.not_before {
+            if parse_time("Not Before", not_before)? > now {
+                return Err(invalid(format!("the message is not valid before {}", not_before)))
+            }
+        }
+        Ok(())
+    }
+
+    // the signature was made by address over this message through personal_sign
+    pub fn verify(&self, signature: &Signature) -> Result<(), DappError> {
+        let signer = recover_signer(self.to_string().as_bytes(), signature)?;
+        if signer != self.address {
+            return Err(DappError::InvalidSignature(format!("signed by {:?}, not {:?}", signer, self.address)))
+        }
+        Ok(())
+    }
+
+    pub fn expires_at(&self) -> Option<i64> {
+        self.expiration_time.as_deref().and_then(|time| parse_time("Expiration Time", time).ok())
+    }
+}
+
+// the text that is signed, see the ABNF of EIP-4361
+impl fmt::Display for SiweMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}{}", self.domain, PREAMBLE)?;
+        writeln!(f, "{}", to_checksum(&self.address, None))?;
+        writeln!(f)?;
+        if let Some(statement) = &self.statement {
+            writeln!(f, "{}", statement)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}{}", URI_TAG, self.uri)?;
+        writeln!(f, "{}{}", VERSION_TAG, self.version)?;
+        writeln!(f, "{}{}", CHAIN_TAG, self.chain_id)?;
+        writeln!(f, "{}{}", NONCE_TAG, self.nonce)?;
+        write!(f, "{}{}", ISSUED_AT_TAG, self.issued_at)?;
+        if let Some(expiration) = &self.expiration_time {
+            write!(f, "\n{}{}", EXPIRATION_TAG, expiration)?;
+        }
+        if let Some(not_before) = &self.not_before {
+            write!(f, "\n{}{}", NOT_BEFORE_TAG, not_before)?;
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, "\n{}{}", REQUEST_ID_TAG, request_id)?;
+        }
+        if !self.resources.is_empty() {
+            write!(f, "\n{}", RESOURCES_TAG)?;
+            for resource in &self.resources {
+                write!(f, "\n- {}", resource)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SiweMessage {
+    type Err = DappError;
+
+    fn from_str(message: &str) -> Result<Self, Self::Err> {
+        let mut lines = message.split('\n').peekable();
+        let mut next = |expected: &str| lines.next()
+            .ok_or_else(|| invalid(format!("missing {}", expected)));
+        let domain = next("preamble")?
+            .strip_suffix(PREAMBLE)
+            .ok_or_else(|| invalid("missing preamble".to_string()))?
+            .to_string();
+        let address_line = next("address")?;
+        let address = DappError::parse_address(address_line)?;
+        if to_checksum(&address, None) != address_line {
+            return Err(invalid("the address must be EIP-55 checksummed".to_string()))
+        }
+        if !next("empty line")?.is_empty() {
+            return Err(invalid("missing empty line after the address".to_string()))
+        }
+        let statement = match next("statement")? {
+            "" => None,
+            statement => {
+                if !next("empty line")?.is_empty() {
+                    return Err(invalid("missing empty line after the statement".to_string()))
+                }
+                Some(statement.to_string())
+            }
+        };
+        let mut tag = |tag: &str| -> Result<String, DappError> {
+            next(tag.trim_end_matches([':', ' ']))?
+                .strip_prefix(tag)
+                .map(str::to_string)
+                .ok_or_else(|| invalid(format!("expected {}", tag.trim_end_matches([':', ' ']))))
+        };
+        let uri = tag(URI_TAG)?;
+        let version = tag(VERSION_TAG)?;
+        let chain_id = tag(CHAIN_TAG)?;
+        let chain_id = chain_id.parse::<u64>()
+            .map_err(|_| invalid(format!("\"{}\" is not a chain id", chain_id)))?;
+        let nonce = tag(NONCE_TAG)?;
+        let issued_at = tag(ISSUED_AT_TAG)?;
+        let mut optional = |tag: &str| lines.next_if(|line| line.starts_with(tag))
+            .map(|line| line[tag.len()..].to_string());
+        let expiration_time = optional(EXPIRATION_TAG);
+        let not_before = optional(NOT_BEFORE_TAG);
+        let request_id = optional(REQUEST_ID_TAG);
+        let mut resources = Vec::new();
+        if lines.next_if_eq(&RESOURCES_TAG).is_some() {
+            while let Some(resource) = lines.next_if(|line| line.starts_with("- ")) {
+                resources.push(resource[2..].to_string());
+            }
+        }
+        if let Some(line) = lines.next() {
+            return Err(invalid(format!("unexpected line \"{}\"", line)))
+        }
+        Ok(SiweMessage {
+            domain,
+            address,
+            statement,
+            uri,
+            version,
+            chain_id,
+            nonce,
+            issued_at,
+            expiration_time,
+            not_before,
+            request_id,
+            resources,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const ISSUED_AT: i64 = 1_700_000_000_000;
+
+    fn message() -> SiweMessage {
+        SiweMessage {
+            domain: "localhost:8080".to_string(),
+            address: "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23".parse().unwrap(),
+            statement: None,
+            uri: "http://localhost:8080/".to_string(),
+            version: "1".to_string(),
+            chain_id: 1,
+            nonce: "abcdef1234".to_string(),
+            issued_at: siwe_time(ISSUED_AT),
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: Vec::new(),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn round_trip_without_statement() {
+        let message = message();
+        let text = message.to_string();
+        assert!(text.starts_with("localhost:8080 wants you to sign in with your Ethereum account:\n0x2c7536E3605D9C16a7a3D7b1898e529396a65c23\n\n\nURI: "));
+        assert_eq!(SiweMessage::from_str(&text).unwrap(), message);
+    }
+
+    #[wasm_bindgen_test]
+    fn round_trip_with_statement_and_resources() {
+        let message = SiweMessage {
+            statement: Some("Sign in to the dapp".to_string()),
+            expiration_time: Some(siwe_time(ISSUED_AT + 60_000)),
+            not_before: Some(siwe_time(ISSUED_AT)),
+            request_id: Some("42".to_string()),
+            resources: vec!["ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/".to_string(), "https://example.com/terms".to_string()],
+            ..message()
+        };
+        let text = message.to_string();
+        assert!(text.ends_with("\nResources:\n- ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/\n- https://example.com/terms"));
+        assert_eq!(SiweMessage::from_str(&text).unwrap(), message);
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_unchecksummed_address() {
+        let text = message().to_string().replace("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23", "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+        assert!(matches!(SiweMessage::from_str(&text), Err(DappError::InvalidSiwe(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_expired_message() {
+        let message = SiweMessage { expiration_time: Some(siwe_time(ISSUED_AT + 60_000)), ..message() };
+        assert!(message.validate(ISSUED_AT + 59_999).is_ok());
+        assert!(matches!(message.validate(ISSUED_AT + 60_000), Err(DappError::InvalidSiwe(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_message_before_not_before() {
+        let message = SiweMessage { not_before: Some(siwe_time(ISSUED_AT + 60_000)), ..message() };
+        assert!(matches!(message.validate(ISSUED_AT + 59_999), Err(DappError::InvalidSiwe(_))));
+        assert!(message.validate(ISSUED_AT + 60_000).is_ok());
+    }
+}
@@ -0,0 +1,60 @@
+use ethers::prelude::*;
+use futures::{stream, StreamExt};
+use gloo_timers::future::TimeoutFuture;
+use crate::ethereum::ProviderHandle;
+
+// wait before subscribing again once the socket dropped
+const RESUBSCRIBE_DELAY_MS: u32 = 3_000;
+
+// Calls on_block with the number of every new head, for as long as the future
+// runs (abort it to stop). Subscribes again when the socket drops.
+pub async fn watch_blocks(provider: ProviderHandle, on_block: impl Fn(u64)) {
+    loop {
+        match provider.get().await {
+            Ok(client) => match client.subscribe_blocks().await {
+                Ok(blocks) => {
+                    let mut blocks = Box::pin(blocks);
+                    while let Some(block) = blocks.next().await {
+                        if let Some(number) = block.number {
+                            on_block(number.as_u64());
+                        }
+                    }
+                }
+                Err(err) => log::warn!("newHeads subscription failed: {}", err),
+            },
+            Err(err) => log::warn!("newHeads subscription failed: {}", err),
+        }
+        TimeoutFuture::new(RESUBSCRIBE_DELAY_MS).await;
+    }
+}
+
+// Same for the Transfer events of token sent or received by account,
+// on_transfer gets the block of the event.
+pub async fn watch_transfers(provider: ProviderHandle, token: Address, account: Address, on_transfer: impl Fn(u64)) {
+    let filter = Filter::new()
+        .address(token)
+        .event("Transfer(address,address,uint256)");
+    let sent = filter.clone().topic1(account);
+    let received = filter.topic2(account);
+    loop {
+        match provider.get().await {
+            Ok(client) => {
+                match futures::join!(client.subscribe_logs(&sent), client.subscribe_logs(&received)) {
+                    (Ok(sent), Ok(received)) => {
+                        let mut logs = stream::select(Box::pin(sent), Box::pin(received));
+                        while let Some(log) = logs.next().await {
+                            // removed by a reorg, the next head brings the new state
+                            if log.removed == Some(true) {
+                                continue
+                            }
+                            on_transfer(log.block_number.unwrap_or_default().as_u64());
+                        }
+                    }
+                    (Err(err), _) | (_, Err(err)) => log::warn!("Transfer subscription failed: {}", err),
+                }
+            }
+            Err(err) => log::warn!("Transfer subscription failed: {}", err),
+        }
+        TimeoutFuture::new(RESUBSCRIBE_DELAY_MS).await;
+    }
+}
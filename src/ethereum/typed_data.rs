@@ -0,0 +1,237 @@
+use ethers::prelude::*;
+use ethers_core::types::transaction::eip712::{Eip712, Types};
+use serde_json::Value;
+use crate::ethereum::DappError;
+
+pub use ethers_core::types::transaction::eip712::TypedData;
+
+// fields of EIP712Domain in the order ethers hashes them, the wallet hashes
+// them in the order of types.EIP712Domain so both have to agree
+const DOMAIN_FIELDS: [&str; 5] = ["name", "version", "chainId", "verifyingContract", "salt"];
+
+// One line of the pretty-printed message, structs get a line without value
+// followed by their fields one level deeper.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedField {
+    pub depth: usize,
+    pub name: String,
+    pub r#type: String,
+    pub value: Option<String>,
+}
+
+// Parses an eth_signTypedData_v4 payload and checks the types are complete,
+// the domain matches its type and the message encodes.
+pub fn parse_typed_data(json: &str) -> Result<TypedData, DappError> {
+    let typed_data: TypedData = serde_json::from_str(json)
+        .map_err(|err| DappError::InvalidTypedData(err.to_string()))?;
+    check_types(&typed_data)?;
+    typed_data_hash(&typed_data)?;
+    Ok(typed_data)
+}
+
+fn check_types(typed_data: &TypedData) -> Result<(), DappError> {
+    let invalid = |message: String| Err(DappError::InvalidTypedData(message));
+    if !typed_data.types.contains_key(&typed_data.primary_type) {
+        return invalid(format!("primaryType {} is not in types", typed_data.primary_type))
+    }
+    for (name, fields) in &typed_data.types {
+        for field in fields {
+            if !is_known_type(&field.r#type, &typed_data.types) {
+                return invalid(format!("unknown type {} of {}.{}", field.r#type, name, field.name))
+            }
+        }
+    }
+    let declared: Vec<&str> = typed_data.types.get("EIP712Domain")
+        .map(|fields| fields.iter().map(|field| field.name.as_str()).collect())
+        .unwrap_or_default();
+    let domain = serde_json::to_value(&typed_data.domain)
+        .map_err(|err| DappError::InvalidTypedData(err.to_string()))?;
+    let present: Vec<&str> = DOMAIN_FIELDS.iter()
+        .copied()
+        .filter(|field| domain.get(field).is_some())
+        .collect();
+    if declared != present {
+        return invalid(format!(
+            "types.EIP712Domain must list the domain fields in this order: {}",
+            present.join(", ")
+        ))
+    }
+    Ok(())
+}
+
+// elementary solidity types, structs from types, and arrays of both
+fn is_known_type(r#type: &str, types: &Types) -> bool {
+    let base = r#type.split('[').next().unwrap_or_default();
+    let sized = |prefix: &str, valid: fn(usize) -> bool| {
+        base.strip_prefix(prefix)
+            .and_then(|size| size.parse::<usize>().ok())
+            .is_some_and(valid)
+    };
+    matches!(base, "address" | "bool" | "string" | "bytes")
+        || sized("bytes", |size| (1..=32).contains(&size))
+        || sized("uint", |size| size % 8 == 0 && (8..=256).contains(&size))
+        || sized("int", |size| size % 8 == 0 && (8..=256).contains(&size))
+        || types.contains_key(base)
+}
+
+// the digest signed by eth_signTypedData_v4
+pub fn typed_data_hash(typed_data: &TypedData) -> Result<H256, DappError> {
+    typed_data.encode_eip712()
+        .map(H256)
+        .map_err(|err| DappError::InvalidTypedData(err.to_string()))
+}
+
+pub fn recover_typed_data_signer(typed_data: &TypedData, signature: &Signature) -> Result<Address, DappError> {
+    let hash = typed_data_hash(typed_data)?;
+    signature.recover(hash)
+        .map_err(|err| DappError::InvalidSignature(err.to_string()))
+}
+
+// domain first, then the message fields as the primary type lists them
+pub fn describe_typed_data(typed_data: &TypedData) -> Vec<TypedField> {
+    let mut fields = Vec::new();
+    if let Ok(Value::Object(domain)) = serde_json::to_value(&typed_data.domain) {
+        fields.push(TypedField { depth: 0, name: "domain".to_string(), r#type: "EIP712Domain".to_string(), value: None });
+        for name in DOMAIN_FIELDS {
+            let value = match (name, typed_data.domain.chain_id) {
+                // serialized as hex
+                ("chainId", Some(chain_id)) => Some(chain_id.to_string()),
+                _ => domain.get(name).map(display_value),
+            };
+            if value.is_some() {
+                fields.push(TypedField { depth: 1, name: name.to_string(), r#type: String::new(), value });
+            }
+        }
+    }
+    let message = Value::Object(typed_data.message.clone().into_iter().collect());
+    fields.push(TypedField { depth: 0, name: "message".to_string(), r#type: typed_data.primary_type.clone(), value: None });
+    describe_struct(&typed_data.types, &typed_data.primary_type, &message, 1, &mut fields);
+    fields
+}
+
+fn describe_struct(types: &Types, struct_type: &str, value: &Value, depth: usize, fields: &mut Vec<TypedField>) {
+    for field in types.get(struct_type).into_iter().flatten() {
+        let value = value.get(&field.name).unwrap_or(&Value::Null);
+        describe_field(types, &field.name, &field.r#type, value, depth, fields);
+    }
+}
+
+fn describe_field(types: &Types, name: &str, r#type: &str, value: &Value, depth: usize, fields: &mut Vec<TypedField>) {
+    // arrays of structs are listed item by item
+    if let (Some(item_type), Some(items)) = (r#type.rsplit_once('[').map(|(item, _)| item), value.as_array()) {
+        if types.contains_key(item_type.split('[').next().unwrap_or_default()) {
+            fields.push(TypedField { depth, name: name.to_string(), r#type: r#type.to_string(), value: None });
+            for (index, item) in items.iter().enumerate() {
+                describe_field(types, &format!("{}[{}]", name, index), item_type, item, depth + 1, fields);
+            }
+            return
+        }
+    }
+    if types.contains_key(r#type) {
+        fields.push(TypedField { depth, name: name.to_string(), r#type: r#type.to_string(), value: None });
+        describe_struct(types, r#type, value, depth + 1, fields);
+    } else {
+        fields.push(TypedField { depth, name: name.to_string(), r#type: r#type.to_string(), value: Some(display_value(value)) });
+    }
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => format!("[{}]", items.iter().map(display_value).collect::<Vec<_>>().join(", ")),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    // the example of EIP-712
+    fn mail() -> Value {
+        json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "Person": [{ "name": "name", "type": "string" }, { "name": "wallet", "type": "address" }],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" },
+                ],
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC",
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!",
+            },
+        })
+    }
+
+    fn check(value: Value) -> Result<(), DappError> {
+        check_types(&serde_json::from_value(value).unwrap())
+    }
+
+    #[wasm_bindgen_test]
+    fn accepts_complete_types() {
+        assert_eq!(check(mail()), Ok(()));
+        let typed_data = parse_typed_data(&mail().to_string()).unwrap();
+        assert_eq!(
+            format!("{:?}", typed_data_hash(&typed_data).unwrap()),
+            "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn accepts_arrays_and_sized_types() {
+        let mut typed_data = mail();
+        typed_data["types"]["Mail"] = json!([
+            { "name": "to", "type": "Person[]" },
+            { "name": "amounts", "type": "uint128[2]" },
+            { "name": "hash", "type": "bytes32" },
+        ]);
+        assert_eq!(check(typed_data), Ok(()));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_missing_primary_type() {
+        let mut typed_data = mail();
+        typed_data["primaryType"] = json!("Letter");
+        assert!(matches!(check(typed_data), Err(DappError::InvalidTypedData(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_unknown_types() {
+        for unknown in ["Animal", "uint7", "uint264", "bytes33", "int0", "uint", "int", "uint[]"] {
+            let mut typed_data = mail();
+            typed_data["types"]["Person"][1]["type"] = json!(unknown);
+            assert_eq!(
+                check(typed_data),
+                Err(DappError::InvalidTypedData(format!("unknown type {} of Person.wallet", unknown)))
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_domain_type_not_matching_the_domain() {
+        let mut missing = mail();
+        missing["types"]["EIP712Domain"].as_array_mut().unwrap().remove(1);
+        assert!(matches!(check(missing), Err(DappError::InvalidTypedData(_))));
+        let mut reordered = mail();
+        reordered["types"]["EIP712Domain"].as_array_mut().unwrap().swap(0, 1);
+        assert!(matches!(check(reordered), Err(DappError::InvalidTypedData(_))));
+    }
+}
@@ -1,61 +1,66 @@
-// You can discretely check your connected account (not listen to chain changes and such..)
+// Shims around the injected wallet selected by the user, account and chain changes are
+// forwarded to the Rust side through subscribeWalletEvents.
 
-async function getProvider() {
-    //console.log(`connecting Metamask from browser`);
-    const provider = new ethers.providers.Web3Provider(window.ethereum);
-    await provider.send("eth_requestAccounts", []);
-    //if(provider) Object.entries(provider).forEach(keyValuePair => {console.log("  ",...keyValuePair)})    
-    return provider;
+// EIP-6963 announced providers by uuid
+const announcedWallets = new Map();
+// wallet picked by the user, falls back to the legacy window.ethereum
+let selectedWallet = null;
+
+function wallet() {
+  const provider = selectedWallet || window.ethereum;
+  if (!provider) {
+    throw { code: 4900, message: "No wallet selected" };
+  }
+  return provider;
+}
+
+export function discoverWallets(onAnnounce) {
+  const listener = (event) => {
+    announcedWallets.set(event.detail.info.uuid, event.detail.provider);
+    onAnnounce(JSON.stringify(event.detail.info));
+  };
+  window.addEventListener("eip6963:announceProvider", listener);
+  window.dispatchEvent(new Event("eip6963:requestProvider"));
+  return listener;
+}
+
+export function stopWalletDiscovery(listener) {
+  window.removeEventListener("eip6963:announceProvider", listener);
 }
 
-async function getSigner() {
-  const provider = await getProvider();
-  const signer = provider.getSigner();
-  //if(signer) Object.entries(signer).forEach(keyValuePair => {console.log("  ",...keyValuePair)})
-  return signer;
+export function hasInjectedWallet() {
+  return window.ethereum !== undefined;
+}
+
+export function selectWallet(uuid) {
+  selectedWallet = announcedWallets.get(uuid) || window.ethereum;
 }
 
 export async function getProviderData() {
-  const provider = await getProvider();
-  let providerWeb3 = provider.provider;
-  // should return an object that i can parse into a ethers(rs)::JsonRpcProvider
-  return providerWeb3;
-}
-
-export async function signMessage() {
-    const signer = await getSigner();
-    const flatSignature = await signer.signMessage("Hello World");
-    console.log(`signed: ${flatSignature}`);
-    return flatSignature;
-}
-
-export async function setRinkeby() {
-  try {
-      await window.ethereum.request({
-        method: 'wallet_switchEthereumChain',
-        params: [
-          {
-            chainId: "0x4",
-          },
-        ],
-      });
-      return true;
-    } catch (switchError) {
-      console.log("error during chain change");
-  }
+  const provider = wallet();
+  await provider.request({ method: "eth_requestAccounts" });
+  // the selected EIP-1193 provider
+  return provider;
 }
 
-////////////////////////////////////////////////////////////////////// ERC20
-export async function transfer(token_address, recipient, amount, decimals) {
-  let abi = [
-      'function transfer(address recipient, uint256 amount) external returns(bool)',
-  ];
-  const signer = await getSigner();
-  const contract = new ethers.Contract(token_address, abi, signer);
-  const amountParsed = ethers.utils.parseUnits(amount, decimals);
-  //console.log(`tx ${tx.hash}`);
-  const tx = await contract.transfer(recipient, amountParsed);
-  //console.log(`tx ${tx.hash}`);
-  return tx.hash;
+// EIP-1193 transport used by the Rust side (src/eip1193.rs), params and result are JSON strings
+export async function request(method, params) {
+  const parsed = JSON.parse(params);
+  const result = await wallet().request({
+    method: method,
+    params: parsed === null ? [] : parsed,
+  });
+  return JSON.stringify(result === undefined ? null : result);
 }
 
+export function subscribeWalletEvents(onAccountsChanged, onChainChanged, onDisconnect) {
+  wallet().on("accountsChanged", onAccountsChanged);
+  wallet().on("chainChanged", onChainChanged);
+  wallet().on("disconnect", onDisconnect);
+}
+
+export function unsubscribeWalletEvents(onAccountsChanged, onChainChanged, onDisconnect) {
+  wallet().removeListener("accountsChanged", onAccountsChanged);
+  wallet().removeListener("chainChanged", onChainChanged);
+  wallet().removeListener("disconnect", onDisconnect);
+}
@@ -1,87 +1,128 @@
 use yew::prelude::*;
-use ethers::core::{types::{U256, Address}, utils::format_units};
+use ethers::core::{types::{U256, Address}, utils::{format_units, to_checksum}};
+use web_sys::HtmlSelectElement;
 use wasm_bindgen::prelude::*;
-use js_sys::Reflect;
-use web_sys::HtmlInputElement;
+use wasm_bindgen_futures::spawn_local;
+use std::rc::Rc;
+use futures::future::{abortable, AbortHandle};
+use gloo_timers::future::TimeoutFuture;
+use yew_ethers_wasm::{
+    components::{address_input::AddressInput, address_name::AddressName, approval_scanner::ApprovalScanner, send_native::SendNative, sign_in::SignIn, sign_message::SignMessage, token_card::TokenCard, token_list_picker::TokenListPicker, typed_data_signer::TypedDataSigner, wallet_picker::WalletPicker},
+    contracts::check_erc20,
+    ethereum::{get_native_balance, watch_blocks, load_networks, find_network, parse_chain_id, DappError, NetworkConfig, ProviderHandle},
+    helpers::short_address,
+    storage::Settings,
+    wallet::{getProviderData, now, selectWallet, sign_in, switch_chain, Eip1193, Session, VerifierHandle, WalletContext, WalletInfo, WalletListeners},
+};
 
-mod components;
-use crate::{ components::{token_card::TokenCard}};
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Tokens,
+    Approvals,
+    Signatures,
+}
 
-mod helpers;
-use helpers::short_address;
+impl Page {
+    // name kept in the preferences
+    fn key(&self) -> &'static str {
+        match self {
+            Page::Tokens => "tokens",
+            Page::Approvals => "approvals",
+            Page::Signatures => "signatures",
+        }
+    }
 
-mod lib;
-use lib::{get_native_balance, fetch_erc20};
+    fn from_key(key: &str) -> Self {
+        match key {
+            "approvals" => Page::Approvals,
+            "signatures" => Page::Signatures,
+            _ => Page::Tokens,
+        }
+    }
+}
 
 enum Msg {
-    ConnectMetamask,
-    ConnectRinkeby,
-    SignMessage,
-    SearchERC20,
-    AddToken(String),
+    SetPage(Page),
+    ConnectWallet(WalletInfo),
+    ChangeWallet,
+    SwitchNetwork,
+    SelectNetwork,
+    CheckToken(String),
+    AddToken(u64, String, Address, String), // chain id, account, token and its symbol
+    RemoveToken(Address),
+    MoveToken(usize, usize), // from and to index in the tracked tokens
+    ClearTokens,
+    ResetSettings,
     FetchBalance(String),
-    SetBalance(U256),
+    SetBalance(String, u64, U256, u64), // account, chain, balance and block it was read at
     SetClient(JsValue),
+    AccountsChanged(Vec<String>),
+    ChainChanged(String),
+    WalletDisconnected,
+    SignIn(Option<String>), // statement of the message
+    SignedIn(Session),
+    SignOut,
+    SessionExpired(String), // issued at of the expired session
+    Error(DappError),
     MessagesUser(String),
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct WalletContext {
-    pub client: Option<JsValue>, // JsValue = provider on JS side
-    pub address: Option<String>,
-    pub chain_id: Option<String>,
-}
-// metamask.js contains wasm-bindgen function shims to call the browser from js side.
-#[wasm_bindgen(module = "/src/js/metamask.js")]
-extern "C" {
-    #[wasm_bindgen(js_name = "getProviderData")]
-    #[wasm_bindgen(catch)]
-    pub async fn getProviderData() -> Result<JsValue, JsValue>;
-   
-    #[wasm_bindgen(js_name = "signMessage")]
-    #[wasm_bindgen(catch)]
-    pub async fn signMessage() -> Result<JsValue, JsValue>;
-
-    #[wasm_bindgen(js_name = "setRinkeby")]
-    #[wasm_bindgen(catch)]
-    pub async fn setRinkeby() -> Result<JsValue, JsValue>;
-
-}
-
 struct Model {
+    page: Page,
     msgs: Option<String>,
-    balance_native: Option<U256>,
+    balance_native: Option<(U256, u64)>,
+    balance_watcher: Option<AbortHandle>,
     wallet_context: WalletContext,
-    erc20_added: Vec<String>,
-    input: NodeRef,
+    wallet_listeners: Option<WalletListeners>,
+    settings: Settings,
+    verifier: Option<VerifierHandle>, // None when Sign-In with Ethereum is not available
+    signing_in: bool,
+    networks: Vec<NetworkConfig>,
+    network_select: NodeRef,
 }
 
 impl Component for Model {
     type Message = Msg;
     type Properties = ();
 
-    fn create(ctx: &Context<Self>) -> Self {
-        // this ensure persistence of the connected wallet (and launches automatically)
-        ctx.link().send_future(async move {
-            Msg::ConnectMetamask
-        });
+    fn create(_ctx: &Context<Self>) -> Self {
+        let networks = load_networks();
+        let settings = Settings::load();
+        // the network used last time, if it is still configured
+        let network = settings.network
+            .and_then(|chain_id| find_network(&networks, chain_id))
+            .or_else(|| networks.first())
+            .expect("No networks configured")
+            .clone();
         Self {
+            page: Page::from_key(&settings.preferences.page),
             msgs: None,
             balance_native: None,
-            erc20_added: Vec::new(),
-            input: NodeRef::default(),
-            wallet_context: WalletContext {
-                client: None,
-                chain_id: None,
-                address: None,
-            }
+            balance_watcher: None,
+            settings,
+            verifier: siwe_verifier(&networks),
+            signing_in: false,
+            networks,
+            network_select: NodeRef::default(),
+            wallet_listeners: None,
+            wallet_context: WalletContext::new(network),
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {            
-            Msg::ConnectMetamask => {
-                log::info!("Connecting!");
+            Msg::SetPage(page) => {
+                self.page = page;
+                self.settings = Settings::update(|settings| settings.preferences.page = page.key().to_string());
+                true
+            }
+            Msg::ConnectWallet(wallet) => {
+                log::info!("Connecting to {}!", wallet.name);
+                // listeners belong to the previous wallet, drop them before switching
+                self.wallet_listeners = None;
+                selectWallet(&wallet.uuid);
+                let name = wallet.name.clone();
+                self.wallet_context.wallet = Some(wallet);
                 ctx.link().send_future(async move {
                     match getProviderData().await {
                         Ok(accs) => {
@@ -89,87 +130,258 @@ impl Component for Model {
                         },
                         Err(err) => {
                             log::error!("Error {:?}", err);
-                            Msg::MessagesUser("No metamask!".to_owned())
+                            Msg::MessagesUser(format!("Could not connect to {}", name))
                         },
                     }
                 });
                 false
             }
-            Msg::ConnectRinkeby => {
-                ctx.link().send_future(async {
-                    match setRinkeby().await {
-                        Ok(_) => Msg::ConnectMetamask, // not updating the state
-                        Err(err) => {
-                            log::error!("Error {:?}", err);
-                            Msg::MessagesUser("Error on change".to_owned())
-                        }
+            Msg::ChangeWallet => {
+                self.wallet_listeners = None;
+                // same network, the connection to it is kept
+                self.wallet_context.wallet = None;
+                self.wallet_context.client = None;
+                self.wallet_context.address = None;
+                self.wallet_context.chain_id = None;
+                self.wallet_context.session = None;
+                self.balance_native = None;
+                self.watch_balance(ctx);
+                true
+            }
+            Msg::SwitchNetwork => {
+                let network = self.wallet_context.network().clone();
+                ctx.link().send_future(async move {
+                    match switch_chain(&network).await {
+                        Ok(()) => Msg::MessagesUser(format!("Switched to {}", network.name)), // chainChanged updates the state
+                        Err(err) => Msg::Error(err),
                     }
                 });
                 false
             }
-            Msg::SignMessage => {
-                ctx.link().send_future(async {
-                    match signMessage().await {
-                        Ok(msg) => {
-                            log::info!("Message signed {:?}", msg);
-                            Msg::MessagesUser("Correctly signed".to_string())
-                        },
-                        Err(err) => {
-                            log::error!("Error signing {:?}", err);
-                            Msg::MessagesUser("Error while signing".to_string())
-                        }
-                    }
-                });
+            Msg::SelectNetwork => {
+                let index = self.network_select.cast::<HtmlSelectElement>().map(|select| select.selected_index());
+                if let Some(network) = index.and_then(|index| self.networks.get(index as usize)) {
+                    self.wallet_context.provider = ProviderHandle::new(network.clone());
+                    self.settings = Settings::update(|settings| settings.network = Some(network.chain_id));
+                    self.balance_native = None;
+                    self.watch_balance(ctx);
+                }
                 true
             }
             Msg::FetchBalance(address) => {
+                let chain_id = self.wallet_context.network().chain_id;
+                let provider = self.wallet_context.provider.clone();
                 ctx.link().send_future(async move {
-                    match get_native_balance(address).await {
-                        Ok(bal) => Msg::SetBalance(bal),
-                        Err(err) => Msg::MessagesUser(err)
+                    let client = match provider.get().await {
+                        Ok(client) => client,
+                        Err(err) => return Msg::Error(err)
+                    };
+                    match get_native_balance(client, address.clone()).await {
+                        Ok((bal, block)) => Msg::SetBalance(address, chain_id, bal, block),
+                        Err(err) => Msg::Error(err)
                     }   
                 });
                 false
             }
-            Msg::SetBalance(bal) => {
-                self.balance_native = Some(bal);
+            Msg::SetBalance(address, chain_id, bal, block) => {
+                // the account or the network changed while fetching
+                if self.wallet_context.address.as_ref() != Some(&address) || self.wallet_context.network().chain_id != chain_id {
+                    return false
+                }
+                self.balance_native = Some((bal, block));
                 true
             }
             Msg::SetClient(provider) => {                
                 self.wallet_context.client = Some(provider);
-                self.wallet_context.address = self.get_address();
-                self.wallet_context.chain_id = self.get_chain_id();
-                let user_address = self.get_address().unwrap();
-                ctx.link().send_message(Msg::FetchBalance(user_address));
+                if self.wallet_listeners.is_none() {
+                    let on_accounts = ctx.link().callback(Msg::AccountsChanged);
+                    let on_chain = ctx.link().callback(Msg::ChainChanged);
+                    let on_disconnect = ctx.link().callback(|_: ()| Msg::WalletDisconnected);
+                    self.wallet_listeners = Some(WalletListeners::subscribe(
+                        move |accounts| on_accounts.emit(accounts),
+                        move |chain| on_chain.emit(chain),
+                        move || on_disconnect.emit(()),
+                    ));
+                }
+                // same path as the wallet events, so the initial state is handled once
+                ctx.link().send_future(async {
+                    match Eip1193::provider().request::<_, Vec<String>>("eth_accounts", ()).await {
+                        Ok(accounts) => Msg::AccountsChanged(accounts),
+                        Err(err) => Msg::Error(err.into())
+                    }
+                });
+                ctx.link().send_future(async {
+                    match Eip1193::provider().request::<_, String>("eth_chainId", ()).await {
+                        Ok(chain) => Msg::ChainChanged(chain),
+                        Err(err) => Msg::Error(err.into())
+                    }
+                });
                 true   
             }
-            Msg::SearchERC20 => {
-                let poss_address = self.input.cast::<HtmlInputElement>().unwrap().value();
-                let address = poss_address.parse::<Address>();
-                match address {
-                    Ok(address_parsed) => {
-                        log::info!("searching {:?}", poss_address);
-                        ctx.link().send_future(async move {
-                            match fetch_erc20(address_parsed).await {
-                                Ok(symbol) => {                        
-                                    Msg::MessagesUser(format!("Found {}!", symbol));
-                                    Msg::AddToken(poss_address)
-                                },
-                                Err(_err) => {
-                                    Msg::MessagesUser("Rejected!".to_string())
-                                }
-                            }    
-                        });                        
-                    },
+            Msg::AccountsChanged(accounts) => {
+                log::info!("Accounts changed {:?}", accounts);
+                self.wallet_context.address = accounts.into_iter().next();
+                // the session belongs to the account that signed in
+                if self.wallet_context.session.as_ref().map(|session| session.address) != self.account() {
+                    self.wallet_context.session = None;
+                }
+                self.balance_native = None;
+                self.watch_balance(ctx);
+                true
+            }
+            Msg::ChainChanged(chain) => {
+                log::info!("Chain changed {:?}", chain);
+                // follow the wallet if it is on a known network
+                if let Some(network) = parse_chain_id(&chain)
+                    .and_then(|chain_id| find_network(&self.networks, chain_id))
+                {
+                    if network != self.wallet_context.network() {
+                        self.wallet_context.provider = ProviderHandle::new(network.clone());
+                        self.settings = Settings::update(|settings| settings.network = Some(network.chain_id));
+                    }
+                }
+                if self.wallet_context.session.as_ref().map(|session| session.chain_id) != parse_chain_id(&chain) {
+                    self.wallet_context.session = None;
+                }
+                self.wallet_context.chain_id = Some(chain);
+                self.balance_native = None;
+                self.watch_balance(ctx);
+                true
+            }
+            Msg::WalletDisconnected => {
+                self.wallet_context.address = None;
+                self.wallet_context.chain_id = None;
+                self.wallet_context.session = None;
+                self.balance_native = None;
+                self.watch_balance(ctx);
+                ctx.link().send_message(Msg::MessagesUser("Wallet disconnected".to_string()));
+                true
+            }
+            Msg::SignIn(statement) => {
+                let chain_id = self.wallet_context.chain_id.as_deref().and_then(parse_chain_id);
+                let (address, chain_id) = match (self.account(), chain_id) {
+                    (Some(address), Some(chain_id)) => (address, chain_id),
+                    _ => return false
+                };
+                let verifier = match self.verifier.clone() {
+                    Some(verifier) => verifier,
+                    None => return false
+                };
+                self.signing_in = true;
+                ctx.link().send_future(async move {
+                    match sign_in(verifier, address, chain_id, statement).await {
+                        Ok(session) => Msg::SignedIn(session),
+                        Err(err) => Msg::Error(err)
+                    }
+                });
+                true
+            }
+            Msg::SignedIn(session) => {
+                self.signing_in = false;
+                // the account or chain changed while signing
+                let chain_id = self.wallet_context.chain_id.as_deref().and_then(parse_chain_id);
+                if self.account() != Some(session.address) || chain_id != Some(session.chain_id) {
+                    return true
+                }
+                if let Some(expires_at) = session.expires_at {
+                    let issued_at = session.issued_at.clone();
+                    let millis = (expires_at - now()).clamp(0, u32::MAX.into()) as u32;
+                    ctx.link().send_future(async move {
+                        TimeoutFuture::new(millis).await;
+                        Msg::SessionExpired(issued_at)
+                    });
+                }
+                self.wallet_context.session = Some(Rc::new(session));
+                true
+            }
+            Msg::SignOut => {
+                self.wallet_context.session = None;
+                true
+            }
+            Msg::SessionExpired(issued_at) => {
+                if self.wallet_context.session.as_ref().map(|session| &session.issued_at) != Some(&issued_at) {
+                    return false
+                }
+                self.wallet_context.session = None;
+                ctx.link().send_message(Msg::MessagesUser("Session expired, sign in again".to_string()));
+                true
+            }
+            Msg::CheckToken(input) => {
+                let account = match self.wallet_context.address.clone() {
+                    Some(account) => account,
+                    None => return false
+                };
+                let token = match DappError::parse_address(&input) {
+                    Ok(token) => token,
                     Err(err) => {
-                        log::info!("Error on input {:?}", err);
-                        Msg::MessagesUser("Error on input".to_string());
+                        ctx.link().send_message(Msg::Error(err));
+                        return false
                     }
+                };
+                let chain_id = self.wallet_context.network().chain_id;
+                if self.settings.is_tracked(chain_id, &account, &token) {
+                    ctx.link().send_message(Msg::MessagesUser(format!("{} is already tracked", short_address(&input))));
+                    return false
                 }
+                log::info!("checking {:?}", token);
+                let provider = self.wallet_context.provider.clone();
+                ctx.link().send_future(async move {
+                    let client = match provider.get().await {
+                        Ok(client) => client,
+                        Err(err) => return Msg::Error(err)
+                    };
+                    match check_erc20(client, token).await {
+                        Ok(metadata) => Msg::AddToken(chain_id, account, token, metadata.symbol),
+                        Err(DappError::Decoding(_)) => {
+                            Msg::MessagesUser(format!("{} does not answer as an ERC20 token", input.trim()))
+                        }
+                        Err(err) => Msg::Error(err)
+                    }
+                });
+                false
+            }
+            Msg::AddToken(chain_id, account, token, symbol) => {
+                let mut added = false;
+                self.settings = Settings::update(|settings| added = settings.track_token(chain_id, &account, &token));
+                let msg = if added {
+                    format!("Added {}", symbol)
+                } else {
+                    format!("{} is already tracked", symbol)
+                };
+                ctx.link().send_message(Msg::MessagesUser(msg));
+                true
+            }
+            Msg::RemoveToken(token) => {
+                if let Some(account) = &self.wallet_context.address {
+                    let chain_id = self.wallet_context.network().chain_id;
+                    self.settings = Settings::update(|settings| settings.untrack_token(chain_id, account, &token));
+                }
+                true
+            }
+            Msg::MoveToken(from, to) => {
+                if let Some(account) = &self.wallet_context.address {
+                    let chain_id = self.wallet_context.network().chain_id;
+                    self.settings = Settings::update(|settings| settings.move_token(chain_id, account, from, to));
+                }
+                true
+            }
+            Msg::ClearTokens => {
+                if let Some(account) = &self.wallet_context.address {
+                    let chain_id = self.wallet_context.network().chain_id;
+                    self.settings = Settings::update(|settings| settings.clear_tokens(chain_id, account));
+                }
+                true
+            }
+            Msg::ResetSettings => {
+                self.settings = Settings::reset();
+                self.page = Page::Tokens;
+                ctx.link().send_message(Msg::MessagesUser("Saved settings cleared".to_string()));
                 true
             }
-            Msg::AddToken(address) => {
-                self.erc20_added.push(address);
+            Msg::Error(err) => {
+                log::error!("{:?}", err);
+                self.signing_in = false;
+                self.msgs = Some(self.error_message(&err));
                 true
             }
             Msg::MessagesUser(msg) => {
@@ -180,122 +392,189 @@ impl Component for Model {
         }
     }
 
+    // html! checks the props of the context provider and of the components
+    // taking callbacks with bare field accesses
+    #[allow(clippy::unnecessary_operation)]
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let Self {msgs, wallet_context, balance_native, erc20_added, input:_} = self;
+        let Self {page, msgs, wallet_context, balance_native, settings, networks, network_select:_, wallet_listeners:_, balance_watcher:_, verifier:_, signing_in:_} = self;
+        let network = wallet_context.network();
         let link = ctx.link();
         html! {
-            <div>
-                <h3>{ "Metamask connection" }</h3>
-                if let Some(address) = &self.get_address() {
-                    <>{short_address(&address)}</>
-                } else {
-                    <button 
-                        onclick={link.callback(|_| {
-                            Msg::ConnectMetamask
-                        })}
-                    >                            
-                        {"Connect"}
-                    </button>
-                }
-                if let Some(chain) =  &self.get_chain_id() {
-                    if chain != "0x4" {
-                        <div>                            
-                            {" connected to chain "}{chain}
-                            <button
-                                onclick={link.callback(|_| {
-                                    Msg::ConnectRinkeby
-                                })}
-                            >{"Change to Rinkeby"}</button>
-                        </div>
-                    } else {
-                        if let Some(balance) = balance_native {
-                            <p>{"Balance: "} { format_units(balance, 18).unwrap() }{" rEth"}</p>
+            <ContextProvider<WalletContext> context={wallet_context.clone()}>
+                <div>
+                    <h3>{ "Wallet connection" }</h3>
+                    if let Some(address) = &wallet_context.address {
+                        if let Some(wallet) = &wallet_context.wallet {
+                            <>{&wallet.name}{": "}</>
+                        }
+                        if let Ok(address) = DappError::parse_address(address) {
+                            <AddressName {address} />
                         }
-                        <button onclick={ctx.link().callback(|_| Msg::SignMessage)}>
-                            {"Sign a message"}
+                        <button onclick={link.callback(|_| Msg::ChangeWallet)}>
+                            {"Change wallet"}
                         </button>
+                        if self.verifier.is_some() {
+                            <SignIn
+                                on_sign_in={link.callback(Msg::SignIn)}
+                                on_sign_out={link.callback(|_| Msg::SignOut)}
+                                signing={self.signing_in}
+                            />
+                            <small>{"Sign-in is checked by a mock verifier in the page (mock-siwe feature), not by a server"}</small>
+                        }
+                    } else {
+                        <WalletPicker on_select={link.callback(Msg::ConnectWallet)} />
+                    }
+                    <div>
+                        {"Network: "}
+                        <select
+                            ref={&self.network_select}
+                            onchange={link.callback(|_| Msg::SelectNetwork)}
+                        >
+                            { for networks.iter().map(|net| html! {
+                                <option selected={net == network}>{&net.name}</option>
+                            })}
+                        </select>
+                    </div>
+                    if let Some(chain) =  &wallet_context.chain_id {
+                        if parse_chain_id(chain) != Some(network.chain_id) {
+                            <div>                            
+                                {" connected to chain "}{chain}
+                                <button
+                                    onclick={link.callback(|_| {
+                                        Msg::SwitchNetwork
+                                    })}
+                                >{format!("Change to {}", network.name)}</button>
+                            </div>
+                        } else {
+                            if let Some((balance, block)) = balance_native {
+                                <p>
+                                    {"Balance: "} { format_units(balance, network.decimals).unwrap_or_default() }
+                                    {" "}{&network.native_symbol}
+                                    <small>{format!(" (last updated at block {})", block)}</small>
+                                </p>
+                            }
+                            if let Some(address) = wallet_context.address.clone() {
+                                <SendNative on_done={link.callback(move |_| Msg::FetchBalance(address.clone()))} />
+                            }
+                        }
                     }
-                }
-
-                <h3>{"ERC20 contracts"}</h3>
-                // as an example, rDAI
-                if let Some(user_address) = &wallet_context.address {
-                    <TokenCard
-                        token_address = {"0xc7AD46e0b8a400Bb3C915120d284AafbA8fc4735".to_string()}
-                        user_address = {user_address.clone()}
-                    />
-                }
-                <h3>{"Add ERC20"}</h3>
-                <input
-                    type="text"
-                    ref={&self.input}
-                    placeholder="Rinkeby ERC20 address"
-                    onchange={ctx.link().callback(|_| Msg::SearchERC20)}
-                />
 
-                if erc20_added.len() > 0 {
                     <div>
-                        {self.list_of_added_erc20()}                    
+                        <button disabled={*page == Page::Tokens} onclick={link.callback(|_| Msg::SetPage(Page::Tokens))}>
+                            {"Tokens"}
+                        </button>
+                        <button disabled={*page == Page::Approvals} onclick={link.callback(|_| Msg::SetPage(Page::Approvals))}>
+                            {"Approvals"}
+                        </button>
+                        <button disabled={*page == Page::Signatures} onclick={link.callback(|_| Msg::SetPage(Page::Signatures))}>
+                            {"Signatures"}
+                        </button>
                     </div>
-                }
-                <h3>{"Messages: "}</h3>
-                if let Some(msg) = msgs {
-                    <h1> { msg } </h1>
-                }
+                    if *page == Page::Approvals {
+                        <ApprovalScanner />
+                    } else if *page == Page::Signatures {
+                        <SignMessage />
+                        <TypedDataSigner />
+                    } else {
+                        <h3>{"Add ERC20"}</h3>
+                        <AddressInput
+                            placeholder={format!("{} ERC20 address", network.name)}
+                            on_change={link.batch_callback(|token: Option<Address>| {
+                                token.map(|token| Msg::CheckToken(to_checksum(&token, None)))
+                            })}
+                        />
+                        if wallet_context.address.is_some() {
+                            <TokenListPicker on_select={link.callback(Msg::CheckToken)} />
+                        }
 
-            </div>
+                        if let Some(address) = &wallet_context.address {
+                            if !settings.tokens(network.chain_id, address).is_empty() {
+                                <div>
+                                    {self.list_of_added_erc20(ctx)}
+                                </div>
+                                <button onclick={link.callback(|_| Msg::ClearTokens)}>
+                                    {"Clear tokens"}
+                                </button>
+                            }
+                        }
+                    }
+                    <h3>{"Messages: "}</h3>
+                    if let Some(msg) = msgs {
+                        <h1> { msg } </h1>
+                    }
+                    <button onclick={link.callback(|_| Msg::ResetSettings)}>
+                        {"Reset saved settings"}
+                    </button>
+
+                </div>
+            </ContextProvider<WalletContext>>
         }
     }
 }
 
 impl Model {
-    fn get_chain_id(&self) -> Option<String> {
-        if let Some(client) = &self.wallet_context.client {
-            match Reflect::get(
-                client.as_ref(), 
-                &JsValue::from("chainId")
-            ) {
-                Ok(val) => {
-                    match val.as_string() {
-                        Some(chain) => Some(chain),
-                        None => None
-                    }
-                }
-                Err(_err) => None
-            }
-        } else {
-            None
+    fn account(&self) -> Option<Address> {
+        self.wallet_context.address.as_deref().and_then(|address| address.parse::<Address>().ok())
+    }
+
+    // refreshes the native balance on every new block of the selected network,
+    // stops the previous watcher (account or network changed)
+    fn watch_balance(&mut self, ctx: &Context<Self>) {
+        if let Some(watcher) = self.balance_watcher.take() {
+            watcher.abort();
         }
+        let address = match self.wallet_context.address.clone() {
+            Some(address) => address,
+            None => return
+        };
+        ctx.link().send_message(Msg::FetchBalance(address.clone()));
+        let on_block = ctx.link().callback(move |_: u64| Msg::FetchBalance(address.clone()));
+        let (watch, watcher) = abortable(watch_blocks(
+            self.wallet_context.provider.clone(),
+            move |block| on_block.emit(block),
+        ));
+        spawn_local(async move {
+            let _ = watch.await;
+        });
+        self.balance_watcher = Some(watcher);
     }
-    fn get_address(&self) -> Option<String> {
-        if let Some(client) = &self.wallet_context.client {
-            match Reflect::get(
-                client.as_ref(), 
-                &JsValue::from("selectedAddress")
-            ) {
-                Ok(val) => {
-                    match val.as_string() {
-                        Some(address) => Some(address),
-                        None => None
-                    }
-                },
-                Err(_err) => None
-            }
-        } else {
-            None
+
+    fn error_message(&self, err: &DappError) -> String {
+        match err {
+            DappError::WalletRejected => "Request cancelled in the wallet".to_string(),
+            DappError::WrongChain { .. } => format!("Switch the wallet to {}", self.wallet_context.network().name),
+            DappError::Transport(_) => format!("Could not reach {}, try again later", self.wallet_context.network().name),
+            err => err.to_string(),
         }
     }
-    fn list_of_added_erc20(&self) -> Html {
-        let erc20_tokens = self.erc20_added
+    fn list_of_added_erc20(&self, ctx: &Context<Self>) -> Html {
+        let address = match &self.wallet_context.address {
+            Some(address) => address,
+            None => return html! {}
+        };
+        let link = ctx.link();
+        let tokens = self.settings.tokens(self.wallet_context.network().chain_id, address);
+        let last = tokens.len().saturating_sub(1);
+        let erc20_tokens = tokens
             .iter()
-            .map(|data| 
+            .enumerate()
+            .filter_map(|(index, data)| Some((index, data, data.parse::<Address>().ok()?)))
+            .map(|(index, data, token)|
         {
             html! {
-                <div>
+                <div key={data.clone()}>
                     <div>
+                        <button disabled={index == 0} onclick={link.callback(move |_| Msg::MoveToken(index, index.saturating_sub(1)))}>
+                            {"Up"}
+                        </button>
+                        <button disabled={index == last} onclick={link.callback(move |_| Msg::MoveToken(index, index + 1))}>
+                            {"Down"}
+                        </button>
+                        <button onclick={link.callback(move |_| Msg::RemoveToken(token))}>
+                            {"Remove"}
+                        </button>
                         <TokenCard
                             token_address = {data.clone()}
-                            user_address = {self.wallet_context.address.as_ref().unwrap().clone()}
                         />
                     </div>
                 </div>
@@ -310,6 +589,19 @@ impl Model {
     }
 }
 
+// Sign-In with Ethereum needs a backend, only the in-page MockVerifier exists
+// so it is behind the mock-siwe feature
+#[cfg(feature = "mock-siwe")]
+fn siwe_verifier(networks: &[NetworkConfig]) -> Option<VerifierHandle> {
+    use yew_ethers_wasm::wallet::{page_origin, MockVerifier};
+    let chain_ids = networks.iter().map(|network| network.chain_id).collect();
+    Some(VerifierHandle::new(MockVerifier::new(&page_origin().0, chain_ids)))
+}
+
+#[cfg(not(feature = "mock-siwe"))]
+fn siwe_verifier(_networks: &[NetworkConfig]) -> Option<VerifierHandle> {
+    None
+}
 
 fn main() {
     wasm_logger::init(wasm_logger::Config::new(log::Level::Trace));
@@ -0,0 +1,362 @@
+use gloo_storage::{LocalStorage, SessionStorage, Storage};
+use ethers::{types::Address, utils::to_checksum};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::{contracts::{EnsStore, MetadataStore, TokenMetadata}, ethereum::FeeSpeed};
+
+// settings live under one localStorage key
+const STORAGE_KEY: &str = "yew-ethers-dapp";
+// the token metadata cache under another one, it is only a cache: no version,
+// dropped when it doesn't parse
+const METADATA_KEY: &str = "yew-ethers-dapp-tokens";
+// ENS answers in sessionStorage, names can change owner so they are read again
+// in the next session
+const ENS_KEY: &str = "yew-ethers-dapp-ens";
+// bump on breaking changes to Settings and add the step to migrate(),
+// new fields only need a #[serde(default)]
+const SCHEMA_VERSION: u64 = 2;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u64,
+    pub network: Option<u64>, // chain id of the selected network
+    tokens: BTreeMap<String, Vec<String>>, // tracked tokens by watchlist_key
+    pub preferences: Preferences,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub page: String, // last page opened
+    pub fee_speed: FeeSpeed,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            network: None,
+            tokens: BTreeMap::new(),
+            preferences: Preferences::default(),
+        }
+    }
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            page: String::new(),
+            fee_speed: FeeSpeed::Normal,
+        }
+    }
+}
+
+// watchlists are per chain and per account
+fn watchlist_key(chain_id: u64, account: &str) -> String {
+    format!("{}:{}", chain_id, account.to_lowercase())
+}
+
+impl Settings {
+    // defaults when nothing is stored or the stored data can't be read
+    pub fn load() -> Self {
+        match LocalStorage::get::<Value>(STORAGE_KEY) {
+            Ok(stored) => migrate(stored).unwrap_or_else(|| {
+                log::warn!("Stored settings could not be read, starting from defaults");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = LocalStorage::set(STORAGE_KEY, self) {
+            log::error!("Could not save the settings: {}", err);
+        }
+    }
+
+    // applies f to the stored settings and saves them, so components holding
+    // their own copy don't overwrite each other's changes
+    pub fn update(f: impl FnOnce(&mut Settings)) -> Self {
+        let mut settings = Self::load();
+        f(&mut settings);
+        settings.save();
+        settings
+    }
+
+    // forgets everything, including what is in localStorage
+    pub fn reset() -> Self {
+        LocalStorage::delete(STORAGE_KEY);
+        LocalStorage::delete(METADATA_KEY);
+        SessionStorage::delete(ENS_KEY);
+        Self::default()
+    }
+
+    pub fn tokens(&self, chain_id: u64, account: &str) -> &[String] {
+        self.tokens
+            .get(&watchlist_key(chain_id, account))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // addresses are compared as addresses, whatever their case
+    pub fn is_tracked(&self, chain_id: u64, account: &str, token: &Address) -> bool {
+        self.tokens(chain_id, account).iter().any(|tracked| same_token(tracked, token))
+    }
+
+    // false when it was already tracked
+    pub fn track_token(&mut self, chain_id: u64, account: &str, token: &Address) -> bool {
+        if self.is_tracked(chain_id, account, token) {
+            return false
+        }
+        self.tokens.entry(watchlist_key(chain_id, account))
+            .or_default()
+            .push(to_checksum(token, None));
+        true
+    }
+
+    pub fn untrack_token(&mut self, chain_id: u64, account: &str, token: &Address) {
+        if let Some(tokens) = self.tokens.get_mut(&watchlist_key(chain_id, account)) {
+            tokens.retain(|tracked| !same_token(tracked, token));
+        }
+    }
+
+    // moves the token at index from to index to, the others keep their order
+    pub fn move_token(&mut self, chain_id: u64, account: &str, from: usize, to: usize) {
+        if let Some(tokens) = self.tokens.get_mut(&watchlist_key(chain_id, account)) {
+            if from < tokens.len() && to < tokens.len() {
+                let token = tokens.remove(from);
+                tokens.insert(to, token);
+            }
+        }
+    }
+
+    pub fn clear_tokens(&mut self, chain_id: u64, account: &str) {
+        self.tokens.remove(&watchlist_key(chain_id, account));
+    }
+}
+
+fn same_token(tracked: &str, token: &Address) -> bool {
+    tracked.parse::<Address>().ok().as_ref() == Some(token)
+}
+
+// Token metadata by chain and address, read once per token.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetadataCache(BTreeMap<String, TokenMetadata>);
+
+impl MetadataCache {
+    pub fn load() -> Self {
+        LocalStorage::get(METADATA_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = LocalStorage::set(METADATA_KEY, self) {
+            log::error!("Could not save the token metadata: {}", err);
+        }
+    }
+
+    pub fn get(&self, chain_id: u64, token: &Address) -> Option<&TokenMetadata> {
+        self.0.get(&address_key(chain_id, token))
+    }
+
+    pub fn insert(&mut self, chain_id: u64, token: &Address, metadata: TokenMetadata) {
+        self.0.insert(address_key(chain_id, token), metadata);
+    }
+}
+
+// The caches above as the stores contracts read and write through.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BrowserCache;
+
+impl MetadataStore for BrowserCache {
+    fn metadata(&self, chain_id: u64, tokens: &[Address]) -> HashMap<Address, TokenMetadata> {
+        let cache = MetadataCache::load();
+        tokens.iter()
+            .filter_map(|token| cache.get(chain_id, token).map(|metadata| (*token, metadata.clone())))
+            .collect()
+    }
+
+    fn insert_metadata(&self, chain_id: u64, metadata: Vec<(Address, TokenMetadata)>) {
+        let mut cache = MetadataCache::load();
+        for (token, metadata) in metadata {
+            cache.insert(chain_id, &token, metadata);
+        }
+        cache.save();
+    }
+}
+
+impl EnsStore for BrowserCache {
+    fn address(&self, chain_id: u64, name: &str) -> Option<Address> {
+        EnsCache::load().address(chain_id, name)
+    }
+
+    fn insert_address(&self, chain_id: u64, name: &str, address: Address) {
+        EnsCache::update(|cache| cache.insert_address(chain_id, name, address));
+    }
+
+    fn name(&self, chain_id: u64, address: &Address) -> Option<Option<String>> {
+        EnsCache::load().name(chain_id, address)
+    }
+
+    fn insert_name(&self, chain_id: u64, address: &Address, name: Option<String>) {
+        EnsCache::update(|cache| cache.insert_name(chain_id, address, name));
+    }
+}
+
+fn address_key(chain_id: u64, address: &Address) -> String {
+    format!("{}:{:?}", chain_id, address)
+}
+
+// ENS names resolved and reverse records looked up, by chain.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnsCache {
+    addresses: BTreeMap<String, Address>, // by "chain:name"
+    names: BTreeMap<String, Option<String>>, // verified primary name by "chain:address", None without one
+}
+
+impl EnsCache {
+    pub fn load() -> Self {
+        SessionStorage::get(ENS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = SessionStorage::set(ENS_KEY, self) {
+            log::error!("Could not save the ENS names: {}", err);
+        }
+    }
+
+    // same as Settings::update, lookups run concurrently
+    pub fn update(f: impl FnOnce(&mut EnsCache)) {
+        let mut cache = Self::load();
+        f(&mut cache);
+        cache.save();
+    }
+
+    pub fn address(&self, chain_id: u64, name: &str) -> Option<Address> {
+        self.addresses.get(&format!("{}:{}", chain_id, name)).copied()
+    }
+
+    pub fn insert_address(&mut self, chain_id: u64, name: &str, address: Address) {
+        self.addresses.insert(format!("{}:{}", chain_id, name), address);
+    }
+
+    // Some(None) when the address is known to have no name
+    pub fn name(&self, chain_id: u64, address: &Address) -> Option<Option<String>> {
+        self.names.get(&address_key(chain_id, address)).cloned()
+    }
+
+    pub fn insert_name(&mut self, chain_id: u64, address: &Address, name: Option<String>) {
+        self.names.insert(address_key(chain_id, address), name);
+    }
+}
+
+// Upgrades stored settings to SCHEMA_VERSION one version at a time.
+// None for data written by a newer version of the app or that doesn't parse.
+fn migrate(mut stored: Value) -> Option<Settings> {
+    let mut version = stored.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > SCHEMA_VERSION {
+        return None
+    }
+    while version < SCHEMA_VERSION {
+        stored = match version {
+            // unversioned data: same fields, version added
+            0 => {
+                stored.as_object_mut()?.insert("version".to_string(), Value::from(1));
+                stored
+            }
+            // watchlists could hold the same token twice in another case
+            1 => {
+                if let Some(watchlists) = stored.get_mut("tokens").and_then(Value::as_object_mut) {
+                    for tokens in watchlists.values_mut() {
+                        *tokens = Value::from(dedupe_tokens(tokens));
+                    }
+                }
+                stored.as_object_mut()?.insert("version".to_string(), Value::from(2));
+                stored
+            }
+            _ => return None,
+        };
+        version += 1;
+    }
+    serde_json::from_value(stored).ok()
+}
+
+// checksummed addresses in their first position, invalid ones dropped
+fn dedupe_tokens(tokens: &Value) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tokens.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|token| token.as_str()?.parse::<Address>().ok())
+        .filter(|token| seen.insert(*token))
+        .map(|token| to_checksum(&token, None))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const DAI: &str = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    const ACCOUNT: &str = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23";
+
+    fn watchlists(tokens: Value) -> Value {
+        json!({ (watchlist_key(1, ACCOUNT)): tokens })
+    }
+
+    #[wasm_bindgen_test]
+    fn migrates_unversioned_settings() {
+        let settings = migrate(json!({
+            "network": 5,
+            "tokens": watchlists(json!([DAI])),
+            "preferences": { "page": "tokens", "fee_speed": "Fast" },
+        })).unwrap();
+        assert_eq!(settings.version, SCHEMA_VERSION);
+        assert_eq!(settings.network, Some(5));
+        assert_eq!(settings.tokens(1, ACCOUNT), [DAI]);
+        assert_eq!(settings.preferences.page, "tokens");
+        assert_eq!(settings.preferences.fee_speed, FeeSpeed::Fast);
+    }
+
+    #[wasm_bindgen_test]
+    fn migrates_unversioned_settings_without_preferences() {
+        let settings = migrate(json!({ "tokens": watchlists(json!([DAI])) })).unwrap();
+        assert_eq!(settings.network, None);
+        assert_eq!(settings.tokens(1, ACCOUNT), [DAI]);
+        assert_eq!(settings.preferences, Preferences::default());
+    }
+
+    #[wasm_bindgen_test]
+    fn merges_duplicate_tokens_of_version_1() {
+        let settings = migrate(json!({
+            "version": 1,
+            "tokens": watchlists(json!([DAI.to_lowercase(), USDC, DAI, "not an address", USDC.to_lowercase()])),
+        })).unwrap();
+        assert_eq!(settings.tokens(1, ACCOUNT), [DAI, USDC]);
+    }
+
+    #[wasm_bindgen_test]
+    fn merges_duplicate_tokens_of_unversioned_settings() {
+        let settings = migrate(json!({ "tokens": watchlists(json!([USDC.to_lowercase(), USDC])) })).unwrap();
+        assert_eq!(settings.tokens(1, ACCOUNT), [USDC]);
+    }
+
+    #[wasm_bindgen_test]
+    fn keeps_current_settings() {
+        let mut settings = Settings { network: Some(1), ..Settings::default() };
+        settings.track_token(1, ACCOUNT, &DAI.parse().unwrap());
+        assert_eq!(migrate(serde_json::to_value(&settings).unwrap()), Some(settings));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_newer_or_unreadable_settings() {
+        assert_eq!(migrate(json!({ "version": SCHEMA_VERSION + 1 })), None);
+        assert_eq!(migrate(json!([DAI])), None);
+        assert_eq!(migrate(json!({ "version": 1, "network": "mainnet" })), None);
+    }
+}
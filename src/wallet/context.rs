@@ -0,0 +1,39 @@
+use yew::prelude::*;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use crate::{ethereum::{NetworkConfig, ProviderHandle}, wallet::{Session, WalletInfo}};
+
+// Shared wallet state, provided by Model to every component through a ContextProvider.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalletContext {
+    pub wallet: Option<WalletInfo>, // wallet picked by the user
+    pub client: Option<JsValue>, // JsValue = provider on JS side
+    pub address: Option<String>,
+    pub chain_id: Option<String>,
+    pub provider: ProviderHandle, // connection to the network selected by the user
+    pub session: Option<Rc<Session>>, // Sign-In with Ethereum of the connected account
+}
+
+impl WalletContext {
+    pub fn new(network: NetworkConfig) -> Self {
+        Self {
+            wallet: None,
+            client: None,
+            address: None,
+            chain_id: None,
+            provider: ProviderHandle::new(network),
+            session: None,
+        }
+    }
+
+    pub fn network(&self) -> &NetworkConfig {
+        self.provider.network()
+    }
+}
+
+// Hook for function components, struct components subscribe through
+// ctx.link().context. yew 0.19 has no #[hook] attribute, hooks are plain functions
+// only called from a function component body.
+pub fn use_wallet() -> WalletContext {
+    use_context::<WalletContext>().expect("use_wallet needs a WalletContext provider")
+}
@@ -0,0 +1,48 @@
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+// uuid of the legacy window.ethereum entry, for wallets that don't announce themselves
+pub const INJECTED_UUID: &str = "injected";
+
+// EIP-6963 provider info, as announced by the wallet
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct WalletInfo {
+    pub uuid: String,
+    pub name: String,
+    pub icon: String, // data URI
+    pub rdns: String,
+}
+
+impl WalletInfo {
+    pub fn injected() -> Self {
+        Self {
+            uuid: INJECTED_UUID.to_owned(),
+            name: "Browser wallet".to_owned(),
+            icon: String::new(),
+            rdns: String::new(),
+        }
+    }
+
+    // announcements travel as JSON strings
+    pub fn from_announcement(info: JsValue) -> Option<Self> {
+        info.as_string()
+            .and_then(|info| serde_json::from_str(&info).ok())
+    }
+}
+
+#[wasm_bindgen(module = "/src/js/metamask.js")]
+extern "C" {
+    // listens to eip6963:announceProvider, returns the listener to stop the discovery
+    #[wasm_bindgen(js_name = "discoverWallets")]
+    pub fn discoverWallets(on_announce: &Closure<dyn Fn(JsValue)>) -> JsValue;
+
+    #[wasm_bindgen(js_name = "stopWalletDiscovery")]
+    pub fn stopWalletDiscovery(listener: &JsValue);
+
+    #[wasm_bindgen(js_name = "hasInjectedWallet")]
+    pub fn hasInjectedWallet() -> bool;
+
+    // every later wallet call (requests, events) goes to the selected provider
+    #[wasm_bindgen(js_name = "selectWallet")]
+    pub fn selectWallet(uuid: &str);
+}
@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use ethers::{prelude::*, types::transaction::eip2718::TypedTransaction};
+use js_sys::{JSON, Reflect};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::{fmt::Debug, str::FromStr};
+use wasm_bindgen::prelude::*;
+use crate::ethereum::{DappError, NetworkConfig, RpcError};
+
+// wallet_switchEthereumChain error for a chain the wallet doesn't know
+const UNRECOGNIZED_CHAIN: i64 = 4902;
+
+#[wasm_bindgen(module = "/src/js/metamask.js")]
+extern "C" {
+    // params and result travel as JSON strings
+    #[wasm_bindgen(js_name = "request")]
+    #[wasm_bindgen(catch)]
+    async fn wallet_request(method: String, params: String) -> Result<JsValue, JsValue>;
+}
+
+// JSON-RPC transport backed by the `request` function of the selected wallet,
+// so Provider<Eip1193> reads, estimates and sends through the user's wallet.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Eip1193;
+
+impl Eip1193 {
+    pub fn provider() -> Provider<Eip1193> {
+        Provider::new(Eip1193)
+    }
+}
+
+// reads code, message and data of the error thrown by the wallet
+fn rpc_error(value: JsValue) -> RpcError {
+    let code = Reflect::get(&value, &JsValue::from("code"))
+        .ok()
+        .and_then(|code| code.as_f64())
+        .map_or(-32603, |code| code as i64); // internal error
+    let message = Reflect::get(&value, &JsValue::from("message"))
+        .ok()
+        .and_then(|message| message.as_string())
+        .unwrap_or_else(|| format!("{:?}", value));
+    let data = Reflect::get(&value, &JsValue::from("data"))
+        .ok()
+        .and_then(|data| JSON::stringify(&data).ok())
+        .and_then(|data| data.as_string())
+        .and_then(|data| serde_json::from_str(&data).ok());
+    RpcError { code, message, data }
+}
+
+fn decoding_error(err: serde_json::Error) -> RpcError {
+    RpcError { code: -32700, message: err.to_string(), data: None } // parse error
+}
+
+#[async_trait(?Send)]
+impl JsonRpcClient for Eip1193 {
+    type Error = RpcError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_string(&params).map_err(decoding_error)?;
+        let result = wallet_request(method.to_owned(), params)
+            .await
+            .map_err(rpc_error)?;
+        let result = result.as_string().unwrap_or_else(|| "null".to_owned());
+        serde_json::from_str(&result).map_err(decoding_error)
+    }
+}
+
+// personal_sign shows the message to the user, ethers' sign_message goes through eth_sign
+pub async fn personal_sign(message: &[u8], address: Address) -> Result<Signature, DappError> {
+    let provider = Eip1193::provider();
+    let signature: String = provider
+        .request("personal_sign", (Bytes::from(message.to_vec()), address))
+        .await?;
+    Signature::from_str(&signature).map_err(|err| DappError::Decoding(err.to_string()))
+}
+
+// typed_data is sent as the JSON the user checked, the wallet shows and hashes it itself
+pub async fn sign_typed_data(typed_data: &str, address: Address) -> Result<Signature, DappError> {
+    let provider = Eip1193::provider();
+    let signature: String = provider
+        .request("eth_signTypedData_v4", (address, typed_data))
+        .await?;
+    Signature::from_str(&signature).map_err(|err| DappError::Decoding(err.to_string()))
+}
+
+// straight to eth_sendTransaction, the caller fills gas and fees, the wallet the nonce
+pub async fn send_transaction(tx: Eip1559TransactionRequest) -> Result<H256, DappError> {
+    let provider = Eip1193::provider();
+    let tx: TypedTransaction = tx.into();
+    Ok(provider.request("eth_sendTransaction", [tx]).await?)
+}
+
+// Switches the wallet to network, adding the network to the wallet first
+// (EIP-3085) when it doesn't know it. chainChanged reports the new chain.
+pub async fn switch_chain(network: &NetworkConfig) -> Result<(), DappError> {
+    let switch = [json!({ "chainId": network.chain_id_hex() })];
+    match JsonRpcClient::request::<_, Value>(&Eip1193, "wallet_switchEthereumChain", switch.clone()).await {
+        Ok(_) => Ok(()),
+        Err(err) if is_unrecognized_chain(&err) => {
+            if network.http_rpc_urls.is_empty() {
+                return Err(DappError::Transport(format!("the wallet does not know {}, add it to the wallet first", network.name)))
+            }
+            let chain = json!({
+                "chainId": network.chain_id_hex(),
+                "chainName": network.name,
+                "nativeCurrency": {
+                    "name": network.native_symbol,
+                    "symbol": network.native_symbol,
+                    "decimals": network.decimals,
+                },
+                "rpcUrls": network.http_rpc_urls,
+                "blockExplorerUrls": network.explorer_url.iter().collect::<Vec<_>>(),
+            });
+            JsonRpcClient::request::<_, Value>(&Eip1193, "wallet_addEthereumChain", [chain]).await.map_err(wallet_error)?;
+            // some wallets add the chain without switching to it
+            JsonRpcClient::request::<_, Value>(&Eip1193, "wallet_switchEthereumChain", switch).await.map_err(wallet_error)?;
+            Ok(())
+        }
+        Err(err) => Err(wallet_error(err)),
+    }
+}
+
+// mobile wallets wrap the code in data.originalError
+fn is_unrecognized_chain(err: &RpcError) -> bool {
+    err.code == UNRECOGNIZED_CHAIN
+        || err.data.as_ref()
+            .and_then(|data| data.pointer("/originalError/code"))
+            .and_then(Value::as_i64) == Some(UNRECOGNIZED_CHAIN)
+}
+
+fn wallet_error(err: RpcError) -> DappError {
+    DappError::from_rpc(err.code, &err.message)
+}
@@ -0,0 +1,62 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+// metamask.js contains wasm-bindgen function shims to call the browser from js side.
+#[wasm_bindgen(module = "/src/js/metamask.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = "getProviderData")]
+    #[wasm_bindgen(catch)]
+    pub async fn getProviderData() -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_name = "subscribeWalletEvents")]
+    fn subscribeWalletEvents(
+        on_accounts_changed: &Closure<dyn Fn(JsValue)>,
+        on_chain_changed: &Closure<dyn Fn(JsValue)>,
+        on_disconnect: &Closure<dyn Fn(JsValue)>,
+    );
+
+    #[wasm_bindgen(js_name = "unsubscribeWalletEvents")]
+    fn unsubscribeWalletEvents(
+        on_accounts_changed: &Closure<dyn Fn(JsValue)>,
+        on_chain_changed: &Closure<dyn Fn(JsValue)>,
+        on_disconnect: &Closure<dyn Fn(JsValue)>,
+    );
+}
+
+// Wallet event handlers, registered on the injected provider for as long as this lives.
+pub struct WalletListeners {
+    accounts_changed: Closure<dyn Fn(JsValue)>,
+    chain_changed: Closure<dyn Fn(JsValue)>,
+    disconnect: Closure<dyn Fn(JsValue)>,
+}
+
+impl WalletListeners {
+    pub fn subscribe(
+        on_accounts: impl Fn(Vec<String>) + 'static,
+        on_chain: impl Fn(String) + 'static,
+        on_disconnect: impl Fn() + 'static,
+    ) -> Self {
+        let listeners = Self {
+            accounts_changed: Closure::wrap(Box::new(move |accounts: JsValue| {
+                on_accounts(
+                    Array::from(&accounts)
+                        .iter()
+                        .filter_map(|account| account.as_string())
+                        .collect()
+                )
+            }) as Box<dyn Fn(JsValue)>),
+            chain_changed: Closure::wrap(Box::new(move |chain: JsValue| {
+                on_chain(chain.as_string().unwrap_or_default())
+            }) as Box<dyn Fn(JsValue)>),
+            disconnect: Closure::wrap(Box::new(move |_| on_disconnect()) as Box<dyn Fn(JsValue)>),
+        };
+        subscribeWalletEvents(&listeners.accounts_changed, &listeners.chain_changed, &listeners.disconnect);
+        listeners
+    }
+}
+
+impl Drop for WalletListeners {
+    fn drop(&mut self) {
+        unsubscribeWalletEvents(&self.accounts_changed, &self.chain_changed, &self.disconnect);
+    }
+}
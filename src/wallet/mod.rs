@@ -0,0 +1,13 @@
+// Browser wallet side: EIP-6963 discovery, the injected provider events,
+// the EIP-1193 transport and the state shared with the components.
+mod context;
+mod discovery;
+mod eip1193;
+mod injected;
+mod session;
+
+pub use context::{WalletContext, use_wallet};
+pub use discovery::{WalletInfo, INJECTED_UUID, discoverWallets, stopWalletDiscovery, hasInjectedWallet, selectWallet};
+pub use eip1193::{Eip1193, personal_sign, send_transaction, sign_typed_data, switch_chain};
+pub use injected::{WalletListeners, getProviderData};
+pub use session::{now, page_origin, sign_in, MockVerifier, Session, SiweVerifier, VerifierHandle};
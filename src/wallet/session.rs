@@ -0,0 +1,245 @@
+use async_trait::async_trait;
+use ethers::{prelude::*, utils::hex};
+use std::{cell::RefCell, collections::HashSet, rc::Rc, str::FromStr};
+use crate::{
+    ethereum::{siwe_time, DappError, SiweMessage},
+    wallet::personal_sign,
+};
+
+// how long a sign in lasts
+const SESSION_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+// Signed in account, shared with the components through the WalletContext.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    pub address: Address,
+    pub chain_id: u64,
+    pub issued_at: String,
+    pub expiration_time: Option<String>,
+    pub expires_at: Option<i64>, // milliseconds since the epoch
+}
+
+impl Session {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+// The backend side of Sign-In with Ethereum: hands out nonces and checks the
+// signed messages before opening a session.
+#[async_trait(?Send)]
+pub trait SiweVerifier {
+    async fn nonce(&self) -> Result<String, DappError>;
+    async fn verify(&self, message: &str, signature: &Signature) -> Result<Session, DappError>;
+}
+
+// Verifier running in the page, with the same checks a backend would make.
+// Stands in for one during development, the app only uses it with the
+// mock-siwe feature.
+pub struct MockVerifier {
+    domain: String,
+    chain_ids: Vec<u64>, // chains sessions can be opened on
+    nonces: RefCell<HashSet<String>>, // issued and not used yet
+}
+
+impl MockVerifier {
+    pub fn new(domain: &str, chain_ids: Vec<u64>) -> Self {
+        Self {
+            domain: domain.to_owned(),
+            chain_ids,
+            nonces: RefCell::new(HashSet::new()),
+        }
+    }
+
+    fn issue(&self, nonce: String) -> String {
+        self.nonces.borrow_mut().insert(nonce.clone());
+        nonce
+    }
+
+    // the checks of verify, now in milliseconds since the epoch
+    fn verify_at(&self, message: &str, signature: &Signature, now: i64) -> Result<Session, DappError> {
+        let message = SiweMessage::from_str(message)?;
+        if message.domain != self.domain {
+            return Err(DappError::InvalidSiwe(format!("signed for {}, not {}", message.domain, self.domain)))
+        }
+        if !self.chain_ids.contains(&message.chain_id) {
+            return Err(DappError::InvalidSiwe(format!("chain {} is not supported", message.chain_id)))
+        }
+        message.validate(now)?;
+        message.verify(signature)?;
+        // each nonce opens one session
+        if !self.nonces.borrow_mut().remove(&message.nonce) {
+            return Err(DappError::InvalidSiwe("unknown or already used nonce".to_string()))
+        }
+        Ok(Session {
+            address: message.address,
+            chain_id: message.chain_id,
+            expires_at: message.expires_at(),
+            issued_at: message.issued_at,
+            expiration_time: message.expiration_time,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl SiweVerifier for MockVerifier {
+    async fn nonce(&self) -> Result<String, DappError> {
+        let mut bytes = [0u8; 16];
+        web_sys::window()
+            .and_then(|window| window.crypto().ok())
+            .and_then(|crypto| crypto.get_random_values_with_u8_array(&mut bytes).ok())
+            .ok_or_else(|| DappError::Transport("no secure random source".to_string()))?;
+        Ok(self.issue(hex::encode(bytes)))
+    }
+
+    async fn verify(&self, message: &str, signature: &Signature) -> Result<Session, DappError> {
+        self.verify_at(message, signature, now())
+    }
+}
+
+// verifier shared by the sign in requests
+#[derive(Clone)]
+pub struct VerifierHandle(Rc<dyn SiweVerifier>);
+
+impl VerifierHandle {
+    pub fn new(verifier: impl SiweVerifier + 'static) -> Self {
+        Self(Rc::new(verifier))
+    }
+}
+
+pub fn now() -> i64 {
+    js_sys::Date::now() as i64
+}
+
+// host and URL of the page, the wallet warns when the message names another site
+pub fn page_origin() -> (String, String) {
+    let location = web_sys::window().map(|window| window.location());
+    let host = location.as_ref().and_then(|location| location.host().ok()).unwrap_or_default();
+    let href = location.as_ref().and_then(|location| location.href().ok()).unwrap_or_default();
+    (host, href)
+}
+
+// Asks the verifier for a nonce, signs the EIP-4361 message with the wallet
+// and has the verifier check it.
+pub async fn sign_in(
+    verifier: VerifierHandle,
+    address: Address,
+    chain_id: u64,
+    statement: Option<String>,
+) -> Result<Session, DappError> {
+    let nonce = verifier.0.nonce().await?;
+    let (domain, uri) = page_origin();
+    let issued_at = now();
+    let message = SiweMessage {
+        domain,
+        address,
+        statement: statement.filter(|statement| !statement.trim().is_empty()),
+        uri,
+        version: "1".to_string(),
+        chain_id,
+        nonce,
+        issued_at: siwe_time(issued_at),
+        expiration_time: Some(siwe_time(issued_at + SESSION_MILLIS)),
+        not_before: None,
+        request_id: None,
+        resources: Vec::new(),
+    };
+    message.validate(issued_at)?;
+    let text = message.to_string();
+    let signature = personal_sign(text.as_bytes(), address).await?;
+    verifier.0.verify(&text, &signature).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::hash_message;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    fn wallet() -> LocalWallet {
+        "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap()
+    }
+
+    fn signed(message: &SiweMessage) -> (String, Signature) {
+        let text = message.to_string();
+        let signature = wallet().sign_hash(hash_message(&text));
+        (text, signature)
+    }
+
+    fn message(verifier: &MockVerifier) -> SiweMessage {
+        SiweMessage {
+            domain: "localhost:8080".to_string(),
+            address: wallet().address(),
+            statement: None,
+            uri: "http://localhost:8080/".to_string(),
+            version: "1".to_string(),
+            chain_id: 1,
+            nonce: verifier.issue("abcdef1234".to_string()),
+            issued_at: siwe_time(NOW),
+            expiration_time: Some(siwe_time(NOW + SESSION_MILLIS)),
+            not_before: None,
+            request_id: None,
+            resources: Vec::new(),
+        }
+    }
+
+    fn verifier() -> MockVerifier {
+        MockVerifier::new("localhost:8080", vec![1, 5])
+    }
+
+    #[wasm_bindgen_test]
+    fn opens_session() {
+        let verifier = verifier();
+        let (text, signature) = signed(&message(&verifier));
+        let session = verifier.verify_at(&text, &signature, NOW).unwrap();
+        assert_eq!(session.address, wallet().address());
+        assert_eq!(session.expires_at, Some(NOW + SESSION_MILLIS));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_other_domain() {
+        let verifier = verifier();
+        let (text, signature) = signed(&SiweMessage { domain: "evil.example".to_string(), ..message(&verifier) });
+        assert!(matches!(verifier.verify_at(&text, &signature, NOW), Err(DappError::InvalidSiwe(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_unsupported_chain() {
+        let verifier = verifier();
+        let (text, signature) = signed(&SiweMessage { chain_id: 137, ..message(&verifier) });
+        assert!(matches!(verifier.verify_at(&text, &signature, NOW), Err(DappError::InvalidSiwe(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_other_signer() {
+        let verifier = verifier();
+        let message = message(&verifier);
+        let (text, _) = signed(&message);
+        let (_, signature) = signed(&SiweMessage { statement: Some("other".to_string()), ..message });
+        assert!(matches!(verifier.verify_at(&text, &signature, NOW), Err(DappError::InvalidSignature(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_expired_message() {
+        let verifier = verifier();
+        let (text, signature) = signed(&message(&verifier));
+        assert!(matches!(verifier.verify_at(&text, &signature, NOW + SESSION_MILLIS), Err(DappError::InvalidSiwe(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_reused_nonce() {
+        let verifier = verifier();
+        let (text, signature) = signed(&message(&verifier));
+        assert!(verifier.verify_at(&text, &signature, NOW).is_ok());
+        assert!(matches!(verifier.verify_at(&text, &signature, NOW), Err(DappError::InvalidSiwe(_))));
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_unknown_nonce() {
+        let verifier = verifier();
+        let (text, signature) = signed(&SiweMessage { nonce: "0123456789".to_string(), ..message(&verifier) });
+        assert!(matches!(verifier.verify_at(&text, &signature, NOW), Err(DappError::InvalidSiwe(_))));
+    }
+}